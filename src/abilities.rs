@@ -2,12 +2,20 @@ use crate::abilities::ResolveKind::{Ability, Spell};
 use crate::card::Zone;
 use crate::{
     action::{Action, Choice},
-    card::{draw_card, put_on_battlefield, put_on_graveyard, put_on_stack, CardType},
-    game::{Game, GameStatus, ObjectId, Value},
-    mana::{Color, Mana},
+    card::{
+        create_token, destroy_card, draw_card, put_in_exile, put_in_hand, put_on_battlefield,
+        put_on_deck_bottom, put_on_deck_top, put_on_graveyard, put_on_stack, shuffle_deck,
+        tap_card, untap_card, Card, CardSubtype, CardType, Counter,
+    },
+    game::{check_state_based_actions, Game, GameStatus, ObjectId, Value, POISON_LIMIT},
+    mana::{Color, Mana, ManaCost, ManaEntry, ManaUsage, ParseManaError},
     turn::{Priority, Step},
 };
-use std::collections::VecDeque;
+use indexmap::IndexSet;
+use std::{
+    cmp,
+    collections::{HashMap, VecDeque},
+};
 
 #[derive(Clone, Debug)]
 pub struct PlayAbility {
@@ -20,6 +28,52 @@ pub struct ActivatedAbility {
     pub cost: Cost,
     pub effect: Effect,
     pub target: Target,
+    pub restrictions: Vec<ActivationRestriction>,
+}
+
+impl ActivatedAbility {
+    /// Mana abilities produce mana without targeting, they don't require priority
+    /// and are resolved without stack.
+    pub fn is_mana_ability(&self) -> bool {
+        matches!(self.effect, Effect::Mana(_))
+            && matches!(self.target, Target::None | Target::Owner)
+    }
+
+    /// Equip ability which attaches the equipment to the target creature
+    /// and can be activated only as a sorcery.
+    pub fn equip(cost: Cost) -> ActivatedAbility {
+        ActivatedAbility {
+            cost,
+            effect: Effect::Attach,
+            target: Target::Creature,
+            restrictions: vec![ActivationRestriction::SorcerySpeed],
+        }
+    }
+
+    /// Loyalty abilities of planeswalkers are activated at sorcery speed
+    /// and only one of them can be activated each turn.
+    pub fn is_loyalty_ability(&self) -> bool {
+        self.cost
+            .flatten()
+            .iter()
+            .any(|cost| matches!(cost, Cost::Loyalty(_)))
+    }
+}
+
+/// Defines when an activated ability can be activated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActivationRestriction {
+    /// Can be activated only any time its controller could cast a sorcery
+    SorcerySpeed,
+
+    /// Can be activated only once each turn
+    OncePerTurn,
+
+    /// Can be activated only during the specified step of its controller's turn
+    Step(Step),
+
+    /// Can be activated only if its controller controls a permanent matching the filter
+    Control(Filter),
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +103,12 @@ pub enum StaticAbility {
     /// Cannot attack
     Defender,
 
+    /// Can block the specified number of additional creatures each combat
+    BlockAdditional(u8),
+
+    /// Can block any number of creatures each combat
+    BlockAny,
+
     /// Deals combat damage before other creatures
     FirstStrike,
 
@@ -60,43 +120,625 @@ pub enum StaticAbility {
 
     /// Any amount of combat damage dealt by this creature is lethal to other creatures
     Deathtouch,
+
+    /// Damage dealt by this source also causes its controller to gain that much life
+    Lifelink,
+
+    /// Deals damage to creatures in the form of -1/-1 counters and to players
+    /// in the form of poison counters
+    Infect,
+
+    /// Deals damage to creatures in the form of -1/-1 counters
+    Wither,
+
+    /// Cannot be destroyed by lethal damage or destroy effects
+    Indestructible,
+
+    /// Cannot be the target of spells or abilities your opponents control
+    Hexproof,
+
+    /// Cannot be the target of spells or abilities
+    Shroud,
+
+    /// Spells and abilities your opponents control that target this permanent
+    /// require the specified cost to be paid in addition
+    ///
+    /// Ward is a triggered ability which counters the spell or ability unless its
+    /// controller pays the cost. It is simplified to an additional cost paid on casting,
+    /// so the permanent cannot be targeted by a player who cannot pay the ward cost.
+    Ward(&'static Cost),
+
+    /// Cannot be targeted, blocked, enchanted or equipped by anything with the specified quality
+    /// and all damage from sources with that quality is prevented
+    Protection(Quality),
+
+    /// Can be cast any time its owner could cast an instant
+    Flash,
+
+    /// Its controller may cast spells of the specified type as though they had flash
+    GrantFlash(CardType),
+
+    /// Can be cast only during the combat phase
+    CastOnlyDuringCombat,
+
+    /// Can be cast only before attackers are declared
+    CastOnlyBeforeAttackers,
+
+    /// As long as this spell is on the stack, players cannot cast other spells
+    /// or activate abilities that are not mana abilities
+    SplitSecond,
+
+    /// Each creature tapped while casting this spell pays for {1} or one mana
+    /// of that creature's color
+    Convoke,
+
+    /// Each card exiled from the graveyard while casting this spell pays for {1}
+    Delve,
+
+    /// Each artifact tapped while casting this spell pays for {1}
+    Improvise,
+
+    /// Spells of the specified type its controller casts cost that much generic mana less
+    CostReduction(Option<CardType>, u8),
+
+    /// Spells of the specified type its controller's opponents cast cost that much
+    /// generic mana more
+    OpponentCostIncrease(Option<CardType>, u8),
+
+    /// Enchanted or equipped creature gets +X/+Y
+    AttachedModifier(i16, i16),
+
+    /// Enchanted or equipped creature has the ability
+    AttachedAbility(&'static StaticAbility),
+}
+
+/// Optional cost that can be paid in addition to the cost of a spell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AdditionalCost {
+    /// Can be paid once to resolve the kicked effects of the spell
+    Kicker(Cost),
+
+    /// Can be paid any number of times, the kicked effects resolve once for each payment
+    Multikicker(Cost),
+
+    /// Returns the spell to its owner's hand instead of the graveyard as it resolves
+    Buyback(Cost),
+}
+
+impl AdditionalCost {
+    pub fn cost(&self) -> &Cost {
+        match self {
+            AdditionalCost::Kicker(cost)
+            | AdditionalCost::Multikicker(cost)
+            | AdditionalCost::Buyback(cost) => cost,
+        }
+    }
 }
 
+/// Optional costs chosen for casting a spell.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CastCosts {
+    pub alternative: Option<AlternativeCost>,
+
+    /// Additional costs to pay, multikicker can be specified several times
+    pub additional: Vec<AdditionalCost>,
+}
+
+impl CastCosts {
+    /// Returns how many times the kicker cost is paid.
+    pub fn kicked(&self) -> usize {
+        self.additional
+            .iter()
+            .filter(|cost| {
+                matches!(
+                    cost,
+                    AdditionalCost::Kicker(_) | AdditionalCost::Multikicker(_)
+                )
+            })
+            .count()
+    }
+
+    pub fn has_buyback(&self) -> bool {
+        self.additional
+            .iter()
+            .any(|cost| matches!(cost, AdditionalCost::Buyback(_)))
+    }
+}
+
+/// Cost that can be paid instead of the cost of a spell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AlternativeCost {
+    /// Can be cast from the graveyard and is exiled instead of going anywhere else
+    Flashback(Cost),
+
+    /// The creature is sacrificed when it enters the battlefield
+    Evoke(Cost),
+
+    /// Can be cast without paying its mana cost
+    Free,
+}
+
+impl AlternativeCost {
+    pub fn cost(&self) -> &Cost {
+        match self {
+            AlternativeCost::Flashback(cost) | AlternativeCost::Evoke(cost) => cost,
+            AlternativeCost::Free => &Cost::None,
+        }
+    }
+}
+
+/// Defines a quality of the object that the protection applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Quality {
+    Color(Color),
+    Type(CardType),
+    Subtype(CardSubtype),
+}
+
+/// Defines whose objects are selected relative to the controller of the spell or ability.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Controller {
+    You,
+    Opponent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn compare<T: PartialOrd>(&self, value: T, other: T) -> bool {
+        match self {
+            Comparison::Less => value < other,
+            Comparison::LessOrEqual => value <= other,
+            Comparison::Equal => value == other,
+            Comparison::GreaterOrEqual => value >= other,
+            Comparison::Greater => value > other,
+        }
+    }
+}
+
+/// Defines which cards or players can be chosen as targets or are selected by an effect,
+/// filters can be combined into more specific ones.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Filter {
+    Any,
+
+    /// Any player, players match only this filter, `Any` and `Controller`
+    Player,
+
+    Quality(Quality),
+    Controller(Controller),
+    Zone(Zone),
+    Tapped,
+    Untapped,
+    Power(Comparison, i16),
+    Toughness(Comparison, i16),
+    ManaValue(Comparison, u8),
+
+    /// Any card other than the source
+    Another,
+
+    And(&'static [Filter]),
+    Or(&'static [Filter]),
+    Not(&'static Filter),
+}
+
+impl Filter {
+    /// Determines whether the card or player matches this filter for the spell or ability
+    /// of the source controlled by the player.
+    pub fn matches(
+        &self,
+        game: &mut Game,
+        card_id: ObjectId,
+        source_id: ObjectId,
+        player_id: ObjectId,
+    ) -> bool {
+        match self {
+            Filter::And(filters) => {
+                return filters
+                    .iter()
+                    .all(|filter| filter.matches(game, card_id, source_id, player_id))
+            }
+            Filter::Or(filters) => {
+                return filters
+                    .iter()
+                    .any(|filter| filter.matches(game, card_id, source_id, player_id))
+            }
+            Filter::Not(filter) => return !filter.matches(game, card_id, source_id, player_id),
+            _ => {}
+        }
+
+        if game.get_player(card_id).is_some() {
+            return match self {
+                Filter::Any | Filter::Player => true,
+                Filter::Controller(Controller::You) => card_id == player_id,
+                Filter::Controller(Controller::Opponent) => card_id != player_id,
+                _ => false,
+            };
+        }
+
+        let card = if let Some(card) = game.get_card(card_id) {
+            card
+        } else {
+            return false;
+        };
+        match self {
+            Filter::Player => false,
+            Filter::Quality(quality) => card.has_quality(quality),
+            Filter::Controller(Controller::You) => card.owner_id == player_id,
+            Filter::Controller(Controller::Opponent) => card.owner_id != player_id,
+            Filter::Zone(zone) => card.zone == *zone,
+            Filter::Tapped => card.state.tapped.current,
+            Filter::Untapped => !card.state.tapped.current,
+            // Only creatures have power and toughness
+            Filter::Power(comparison, power) => {
+                card.is_creature() && comparison.compare(card.state.power.current, *power)
+            }
+            Filter::Toughness(comparison, toughness) => {
+                card.is_creature() && comparison.compare(card.state.toughness.current, *toughness)
+            }
+            Filter::ManaValue(comparison, value) => {
+                let mana_value = card.cost.get_mana_cost().map_or(0, |cost| cost.cmc());
+                comparison.compare(mana_value, *value)
+            }
+            Filter::Another => card_id != source_id,
+            _ => true,
+        }
+    }
+
+    /// Determines whether the target matches the filter. Cards must be on the battlefield
+    /// unless the branch of the filter they match specifies their zone.
+    pub fn matches_target(
+        &self,
+        game: &mut Game,
+        card_id: ObjectId,
+        source_id: ObjectId,
+        player_id: ObjectId,
+    ) -> bool {
+        if game.get_player(card_id).is_some() {
+            return self.matches(game, card_id, source_id, player_id);
+        }
+        let on_battlefield = game
+            .get_card(card_id)
+            .is_some_and(|card| card.zone == Zone::Battlefield);
+        match self.matches_zone(game, card_id, source_id, player_id) {
+            Some(has_zone) => has_zone || on_battlefield,
+            None => false,
+        }
+    }
+
+    /// Returns None if the card does not match the filter, otherwise whether
+    /// the branch of the filter it matches specifies the zone of the card.
+    fn matches_zone(
+        &self,
+        game: &mut Game,
+        card_id: ObjectId,
+        source_id: ObjectId,
+        player_id: ObjectId,
+    ) -> Option<bool> {
+        match self {
+            Filter::And(filters) => {
+                let mut has_zone = false;
+                for filter in filters.iter() {
+                    has_zone |= filter.matches_zone(game, card_id, source_id, player_id)?;
+                }
+                Some(has_zone)
+            }
+            Filter::Or(filters) => {
+                let mut matched = None;
+                for filter in filters.iter() {
+                    match filter.matches_zone(game, card_id, source_id, player_id) {
+                        Some(true) => return Some(true),
+                        Some(false) => matched = Some(false),
+                        None => {}
+                    }
+                }
+                matched
+            }
+            filter => filter
+                .matches(game, card_id, source_id, player_id)
+                .then_some(matches!(filter, Filter::Zone(_))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Cost {
     #[default]
     None,
     Mana(&'static str),
     Tap(Target),
     Sacrifice(Target),
+    Discard(Target),
+    Life(u16),
+    ExileFromGraveyard(Target),
+    RemoveCounters(Counter, u16),
+    ReturnToHand(Target),
+
+    /// Puts loyalty counters on the source or removes them if negative
+    Loyalty(i16),
 
     // Must pay all
     And(&'static [Cost]),
 }
 
+impl Cost {
+    /// Returns the total mana cost required by this cost.
+    pub fn get_mana_cost(&self) -> Result<ManaCost, ParseManaError> {
+        match self {
+            Cost::Mana(mana) => mana.parse(),
+            Cost::And(costs) => costs.iter().try_fold(ManaCost::new(), |total, cost| {
+                Ok(total + cost.get_mana_cost()?)
+            }),
+            _ => Ok(ManaCost::new()),
+        }
+    }
+
+    /// Returns all costs which must be paid, expanding the combined costs.
+    pub fn flatten(&self) -> Vec<Cost> {
+        match self {
+            Cost::None => vec![],
+            Cost::And(costs) => costs.iter().flat_map(|cost| cost.flatten()).collect(),
+            cost => vec![cost.clone()],
+        }
+    }
+
+    /// Determines whether the cost is paid with a chosen card.
+    pub fn requires_card(&self) -> bool {
+        matches!(
+            self,
+            Cost::Tap(_)
+                | Cost::Sacrifice(_)
+                | Cost::Discard(_)
+                | Cost::ExileFromGraveyard(_)
+                | Cost::ReturnToHand(_)
+        )
+    }
+
+    /// Determines whether the cost is paid with the source of the ability,
+    /// so the source can pay several such costs.
+    pub fn requires_source(&self) -> bool {
+        matches!(
+            self,
+            Cost::Tap(Target::Source)
+                | Cost::Sacrifice(Target::Source)
+                | Cost::Discard(Target::Source)
+                | Cost::ExileFromGraveyard(Target::Source)
+                | Cost::ReturnToHand(Target::Source)
+        )
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum Effect {
     #[default]
     None,
     Mana(Mana),
-    Damage(u16),
-    Discard(usize),
-    Draw(usize),
+    Damage(Amount),
+    Discard(Amount),
+    Draw(Amount),
+
+    /// Its controller gains the amount of life
+    GainLife(Amount),
+
+    /// The target player loses the amount of life, which is not damage
+    LoseLife(Amount),
+
+    /// The life total of the target player becomes the amount
+    SetLife(i16),
+
+    /// The target creature gets +X/+Y until end of turn
+    Pump(i16, i16),
+
+    /// Puts the number of cards from the top of the player's library into their graveyard
+    Mill(Amount),
+
+    /// Looks at the number of cards from the top of the player's library and puts
+    /// any of them on the bottom and the rest back on top in any order
+    Scry(usize),
+
+    /// Looks at the number of cards from the top of the player's library and puts
+    /// any of them into the graveyard and the rest back on top in any order
+    Surveil(usize),
+
+    /// Searches the player's library for a card matching the filter, puts it into the zone
+    /// and shuffles the library
+    Search(Filter, Zone),
+
+    /// Reveals the number of cards from the top of the player's library, puts one of them
+    /// into their hand and the rest on the bottom of the library
+    RevealTop(usize),
+
+    /// Destroys the target permanent
+    Destroy,
+
+    /// Exiles the target permanent
+    Exile,
+
+    /// Returns the target permanent to its owner's hand
+    ReturnToHand,
+
+    /// Puts the target permanent on top of its owner's library
+    PutOnLibrary,
+
+    Tap,
+    Untap,
+
+    /// Applies the effect to each permanent matching the filter
+    All(Filter, Box<Effect>),
+
+    /// Attaches the source equipment to the target creature its controller controls
+    Attach,
+
+    /// Creates the number of tokens controlled by the owner or the target player,
+    /// or the number of copies of the target permanent
+    CreateToken(Token, usize),
+
+    /// Deals the amount of damage divided among the targets as chosen on cast
+    DividedDamage(Amount),
+
+    /// Resolves once for each time the kicker cost of the spell was paid
+    Kicked(Box<Effect>),
+
+    /// The caster chooses the number of different modes as the spell is cast,
+    /// and a target for each chosen mode
+    Modal(usize, Vec<Mode>),
+
+    /// Resolves the effect of the mode chosen at the position with the target chosen for it
+    ChosenMode(usize, Box<Effect>),
+
+    /// Resolves the effect for the player of a group player target, who is its target
+    /// and the player it applies to
+    ForPlayer(ObjectId, Box<Effect>),
+
+    /// Resolves the first effect if the condition is met as it resolves, otherwise the second
+    If(EffectCondition, Box<Effect>, Box<Effect>),
 
     And(VecDeque<Effect>),
 }
 
 impl Effect {
+    /// Returns the total amount of mana produced by this effect.
+    pub fn get_mana(&self) -> Mana {
+        match self {
+            Effect::Mana(mana) => *mana,
+            Effect::And(effects) => effects
+                .iter()
+                .fold(Mana::new(), |total, effect| total + effect.get_mana()),
+            _ => Mana::new(),
+        }
+    }
+
+    /// Replaces the kicked effects with the effects resolved for the number of times
+    /// the kicker cost was paid.
+    pub fn kick(&self, times: usize) -> Effect {
+        match self {
+            Effect::Kicked(effect) => match times {
+                0 => Effect::None,
+                1 => effect.kick(times),
+                _ => Effect::And(vec![effect.kick(times); times].into()),
+            },
+            Effect::And(effects) => {
+                let mut kicked = VecDeque::new();
+                for effect in effects.iter() {
+                    match effect.kick(times) {
+                        Effect::None => {}
+                        Effect::And(effects) => kicked.extend(effects),
+                        effect => kicked.push_back(effect),
+                    }
+                }
+                Effect::And(kicked)
+            }
+            effect => effect.clone(),
+        }
+    }
+
+    /// Replaces the modal effect with the effects of the modes chosen by their indexes,
+    /// each resolved with the target chosen for its position.
+    pub fn choose_modes(&self, chosen: &[usize]) -> Effect {
+        match self {
+            Effect::Modal(_, modes) => Effect::And(
+                chosen
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(position, index)| {
+                        modes.get(*index).map(|mode| mode.effect.in_mode(position))
+                    })
+                    .collect(),
+            ),
+            Effect::And(effects) => Effect::And(
+                effects
+                    .iter()
+                    .map(|effect| effect.choose_modes(chosen))
+                    .collect(),
+            ),
+            effect => effect.clone(),
+        }
+    }
+
+    /// Determines whether the effect applies to the target of its spell or ability,
+    /// rather than only to its controller or to each matching permanent.
+    pub fn uses_target(&self) -> bool {
+        !matches!(
+            self,
+            Effect::None
+                | Effect::Mana(_)
+                | Effect::GainLife(_)
+                | Effect::All(_, _)
+                | Effect::ChosenMode(_, _)
+                | Effect::ForPlayer(_, _)
+        )
+    }
+
+    /// Replaces the amounts of this effect with their values computed for the action.
+    fn evaluate(&self, game: &mut Game, action: &Action) -> Effect {
+        match self {
+            Effect::Damage(amount) => Effect::Damage(amount.evaluate(game, action)),
+            Effect::Discard(amount) => Effect::Discard(amount.evaluate(game, action)),
+            Effect::Draw(amount) => Effect::Draw(amount.evaluate(game, action)),
+            Effect::GainLife(amount) => Effect::GainLife(amount.evaluate(game, action)),
+            Effect::LoseLife(amount) => Effect::LoseLife(amount.evaluate(game, action)),
+            Effect::Mill(amount) => Effect::Mill(amount.evaluate(game, action)),
+            Effect::All(filter, effect) => {
+                Effect::All(filter.clone(), Box::new(effect.evaluate(game, action)))
+            }
+            Effect::ChosenMode(position, effect) => {
+                Effect::ChosenMode(*position, Box::new(effect.evaluate(game, action)))
+            }
+            Effect::ForPlayer(player_id, effect) => {
+                let player_action = action.for_player(*player_id);
+                Effect::ForPlayer(*player_id, Box::new(effect.evaluate(game, &player_action)))
+            }
+            effect => effect.clone(),
+        }
+    }
+
+    fn in_mode(&self, position: usize) -> Effect {
+        match self {
+            Effect::And(effects) => Effect::And(
+                effects
+                    .iter()
+                    .map(|effect| effect.in_mode(position))
+                    .collect(),
+            ),
+            Effect::If(condition, effect, otherwise) => Effect::If(
+                condition.clone(),
+                Box::new(effect.in_mode(position)),
+                Box::new(otherwise.in_mode(position)),
+            ),
+            Effect::Kicked(effect) => Effect::Kicked(Box::new(effect.in_mode(position))),
+            effect => Effect::ChosenMode(position, Box::new(effect.clone())),
+        }
+    }
+
     pub fn get_required_choice(&self) -> Choice {
         match self {
             Effect::Mana(mana) => {
                 if mana.has(&Color::Any) {
-                    Choice::Mana(mana.clone())
+                    Choice::Mana(*mana)
                 } else {
                     Choice::None
                 }
             }
-            Effect::Discard(count) => Choice::And(vec![Choice::Card(0); *count]),
+            // The number of cards is known once the amount is evaluated as the effect resolves
+            Effect::Discard(count) => {
+                Choice::And(vec![Choice::Card(0); count.value().unwrap_or(0) as usize])
+            }
+            Effect::Scry(_) | Effect::Surveil(_) => Choice::Arrange {
+                top: vec![],
+                bottom: vec![],
+                graveyard: vec![],
+            },
+            Effect::Search(_, _) | Effect::RevealTop(_) => Choice::Card(0),
+            Effect::ChosenMode(_, effect) | Effect::ForPlayer(_, effect) => {
+                effect.get_required_choice()
+            }
             Effect::And(effects) => {
                 let mut choices = vec![];
                 for effect in effects {
@@ -109,15 +751,195 @@ impl Effect {
     }
 }
 
+/// Defines the amount of an effect, computed from the game state as the effect resolves.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Amount {
+    Fixed(u16),
+
+    /// The value chosen for X as the spell was cast
+    X,
+
+    /// The number of permanents matching the filter
+    Permanents(Filter),
+
+    /// The number of cards in its controller's hand
+    CardsInHand,
+
+    /// The power of the source, or its last known power if it left the battlefield
+    SourcePower,
+
+    /// The total life its controller gained this turn
+    LifeGained,
+}
+
+impl Amount {
+    /// Returns the fixed amount or None if the amount computed from the game state
+    /// has not been evaluated yet.
+    pub fn value(&self) -> Option<u16> {
+        match self {
+            Amount::Fixed(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the amount of an effect being resolved, which must have been evaluated.
+    fn resolved(&self) -> Result<u16, ResolveError> {
+        self.value().ok_or(ResolveError::UnevaluatedAmount)
+    }
+
+    /// Computes the amount for the action from the current game state.
+    pub fn evaluate(&self, game: &mut Game, action: &Action) -> Amount {
+        Amount::Fixed(self.compute(game, action))
+    }
+
+    /// Computes the value of the amount for the action from the current game state.
+    pub fn compute(&self, game: &mut Game, action: &Action) -> u16 {
+        let count = |count: usize| u16::try_from(count).unwrap_or(u16::MAX);
+        match self {
+            Amount::Fixed(value) => *value,
+            Amount::X => action.choices.x as u16,
+            Amount::Permanents(filter) => count(get_permanents(game, action, filter).len()),
+            Amount::CardsInHand => game
+                .get_player(action.player_id)
+                .map_or(0, |player| count(player.hand.len())),
+            Amount::SourcePower => {
+                // A source which left the battlefield uses its last known power
+                let in_play = game
+                    .cards
+                    .get(&action.card_id)
+                    .is_some_and(|card| card.zone == Zone::Battlefield || card.zone == Zone::Stack);
+                let source = if in_play {
+                    game.cards.get(&action.card_id)
+                } else {
+                    game.last_known
+                        .get(&action.card_id)
+                        .or_else(|| game.cards.get(&action.card_id))
+                };
+                source.map_or(0, |card| cmp::max(card.state.power.current, 0) as u16)
+            }
+            Amount::LifeGained => game
+                .turn
+                .life_gained
+                .get(&action.player_id)
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Defines one mode of a modal effect.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mode {
+    pub effect: Effect,
+    pub target: Target,
+}
+
+/// Defines the game state checked as a conditional effect resolves.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EffectCondition {
+    /// Its controller controls a permanent matching the filter
+    Control(Filter),
+
+    /// Its controller has at least the number of cards in their graveyard
+    Graveyard(usize),
+}
+
+impl EffectCondition {
+    pub fn check(&self, game: &mut Game, action: &Action) -> bool {
+        match self {
+            EffectCondition::Control(filter) => {
+                controls(game, action.player_id, action.card_id, filter)
+            }
+            EffectCondition::Graveyard(count) => game
+                .get_player(action.player_id)
+                .is_some_and(|player| player.graveyard.len() >= *count),
+        }
+    }
+}
+
+/// Defines the token created by an effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Token {
+    /// Creature token of the color with the subtype, power, toughness and static abilities
+    Creature {
+        subtype: CardSubtype,
+        color: Color,
+        power: i16,
+        toughness: i16,
+        abilities: &'static [StaticAbility],
+    },
+
+    /// Colorless artifact that can be sacrificed for one mana of any color
+    Treasure,
+
+    /// Copy of the target permanent
+    Copy,
+}
+
+impl Token {
+    /// Returns the card of the token controlled by the player. Copies are created from
+    /// the copied card, so None is returned if it does not exist.
+    pub fn get_card(
+        &self,
+        game: &mut Game,
+        controller_id: ObjectId,
+        copied_id: Option<ObjectId>,
+    ) -> Option<Card> {
+        let card = match self {
+            Token::Creature {
+                subtype,
+                color,
+                power,
+                toughness,
+                abilities,
+            } => {
+                let mut card = Card::new_creature(controller_id, *power, *toughness);
+                card.name = format!("{subtype:?}");
+                card.subtypes.insert(*subtype);
+                card.color_indicator = Some(IndexSet::from([*color]));
+                card.static_abilities.extend(abilities.iter().cloned());
+                card
+            }
+            Token::Treasure => {
+                let mut card = Card::new_artifact(controller_id);
+                card.name = "Treasure".to_owned();
+                card.subtypes.insert(CardSubtype::Treasure);
+                card.activated_abilities.push(ActivatedAbility {
+                    cost: Cost::And(&[Cost::Tap(Target::Source), Cost::Sacrifice(Target::Source)]),
+                    effect: Effect::Mana(Mana::from("*")),
+                    target: Target::None,
+                    restrictions: vec![],
+                });
+                card
+            }
+            Token::Copy => {
+                let mut card = game.get_card(copied_id?)?.clone();
+                card.owner_id = controller_id;
+                card
+            }
+        };
+        Some(card)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Condition {
     Tap(Target),
     Untap(Target),
     Draw,
     Phase(Step),
+
+    /// Triggers when the source or any card enters the battlefield
+    EnterBattlefield(Target),
+
+    /// Triggers when the source or any card leaves the battlefield
+    LeaveBattlefield(Target),
+
+    /// Triggers when the source or any card is put into a graveyard from the battlefield
+    Dies(Target),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Target {
     None,
     Source,
@@ -125,26 +947,96 @@ pub enum Target {
     Player,
     Creature,
 
+    /// Target card on the battlefield
+    Card,
+
     // Defines that any of the specified targets can be selected
     AnyOf(&'static [Target]),
-}
 
-#[derive(Clone, Debug)]
-pub struct Resolve {
-    pub effect: Effect,
-    pub action: Action,
-    pub player_id: ObjectId,
-    pub kind: ResolveKind,
-}
+    /// Target player other than the controller
+    Opponent,
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub enum ResolveKind {
-    Spell(ObjectId),
-    Ability,
-}
+    /// Target planeswalker on the battlefield
+    Planeswalker,
 
-// TODO: Change this name
-#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    /// Target creature, player or planeswalker
+    Any,
+
+    /// Applies to each player in APNAP order without targeting
+    EachPlayer,
+
+    /// Applies to each opponent of the controller in APNAP order without targeting
+    EachOpponent,
+
+    /// Defines a target card or player matching the filter, cards must be
+    /// on the battlefield unless the branch of the filter they match specifies their zone
+    Filtered(Filter),
+
+    /// Defines several target slots, each chosen with its own requirement
+    Slots(&'static [TargetSlot]),
+}
+
+impl Target {
+    /// Determines whether the requirement chooses a single target, as opposed to
+    /// no target, the source or its controller, or several target slots.
+    pub fn is_targeted(&self) -> bool {
+        matches!(
+            self,
+            Target::Player
+                | Target::Opponent
+                | Target::Creature
+                | Target::Planeswalker
+                | Target::Any
+                | Target::Card
+                | Target::AnyOf(_)
+                | Target::Filtered(_)
+        )
+    }
+
+    /// Returns the filter which selects the objects matching this requirement,
+    /// or None for the requirements which are not defined by a filter.
+    pub fn get_filter(&self) -> Option<Filter> {
+        const CREATURE: Filter = Filter::Quality(Quality::Type(CardType::Creature));
+        const PLANESWALKER: Filter = Filter::Quality(Quality::Type(CardType::Planeswalker));
+        let filter = match self {
+            Target::Player => Filter::Player,
+            Target::Opponent => {
+                Filter::And(&[Filter::Player, Filter::Controller(Controller::Opponent)])
+            }
+            Target::Creature => CREATURE,
+            Target::Planeswalker => PLANESWALKER,
+            Target::Any => Filter::Or(&[Filter::Player, CREATURE, PLANESWALKER]),
+            Target::Filtered(filter) => filter.clone(),
+            _ => return None,
+        };
+        Some(filter)
+    }
+}
+
+/// Defines a number of different targets chosen with the same requirement.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TargetSlot {
+    pub target: Target,
+    pub min: usize,
+    pub max: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Resolve {
+    pub effect: Effect,
+    pub action: Action,
+    pub player_id: ObjectId,
+    pub kind: ResolveKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResolveKind {
+    Spell(ObjectId),
+    Ability,
+}
+
+// TODO: Change this name
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct ResolveChoice {
     pub choice: Choice,
     pub player_id: ObjectId,
@@ -154,10 +1046,12 @@ pub struct ResolveChoice {
 impl ResolveChoice {
     pub fn valid_choice(&self, game: &mut Game) -> bool {
         match &self.effect {
-            Effect::Mana(mana) => !mana.has(&Color::Any) || self.choice.validate_mana(mana),
+            Effect::Mana(mana) => {
+                !mana.has(&Color::Any) || self.choice.validate_mana(&ManaCost::from(mana))
+            }
             Effect::Discard(card_count) => match &self.choice {
                 Choice::Card(card_id) => {
-                    if *card_count != 1 {
+                    if card_count.value() != Some(1) {
                         return false;
                     }
                     if let Some(card) = game.get_card(*card_id) {
@@ -166,7 +1060,7 @@ impl ResolveChoice {
                     false
                 }
                 Choice::And(choices) => {
-                    choices.len() == *card_count
+                    card_count.value() == Some(choices.len() as u16)
                         && choices.iter().all(|choice| {
                             if let Choice::Card(card_id) = choice {
                                 if let Some(card) = game.get_card(*card_id) {
@@ -193,36 +1087,108 @@ pub enum ResolveError {
 
     UnknownEffect,
     UnknownActionOwner,
+
+    /// Amount computed from the game state was not evaluated before resolving the effect
+    UnevaluatedAmount,
 }
 
 pub fn create_card_action(
     game: &mut Game,
     card_id: ObjectId,
     player_id: ObjectId,
+) -> Option<Action> {
+    create_card_action_with_costs(game, card_id, player_id, CastCosts::default())
+}
+
+/// Creates the action to cast the card paying the chosen alternative and additional costs.
+/// Returns None if the card does not have the chosen costs.
+pub fn create_card_action_with_costs(
+    game: &mut Game,
+    card_id: ObjectId,
+    player_id: ObjectId,
+    costs: CastCosts,
 ) -> Option<Action> {
     if !can_play_card(game, card_id, player_id) {
         return None;
     }
 
-    let card = if let Some(card) = game.get_card(card_id) {
-        card
-    } else {
+    let card = game.get_card(card_id)?;
+
+    let from_graveyard = matches!(costs.alternative, Some(AlternativeCost::Flashback(_)));
+    if from_graveyard != (card.zone == Zone::Graveyard) {
+        // Only spells with flashback can be cast from the graveyard
         return None;
-    };
+    }
+
+    if let Some(alternative) = &costs.alternative {
+        if !card.alternative_costs.contains(alternative) {
+            return None;
+        }
+    }
+    for (index, additional) in costs.additional.iter().enumerate() {
+        let repeated = costs.additional[..index].contains(additional);
+        if !card.additional_costs.contains(additional)
+            || (repeated && !matches!(additional, AdditionalCost::Multikicker(_)))
+        {
+            return None;
+        }
+    }
 
     if let Some(resolve) = &card.play_ability {
-        let cost = card.cost.clone();
+        let cost = match &costs.alternative {
+            Some(alternative) => alternative.cost().clone(),
+            None => card.cost.clone(),
+        };
         let target = resolve.target.clone();
 
         let mut action = Action::new(player_id, card_id);
         action.set_required_cost(cost);
         action.set_required_target(target);
+        match &resolve.effect {
+            Effect::Modal(count, modes) => {
+                let targets = modes.iter().map(|mode| mode.target.clone()).collect();
+                action.set_required_modes(*count, targets);
+            }
+            Effect::DividedDamage(amount) => action.set_required_division(amount.clone()),
+            _ => {}
+        }
+        action.set_mana_usage(ManaUsage::Spell(card.types.clone()));
+        action.set_cast_costs(costs);
         Some(action)
     } else {
         None
     }
 }
 
+/// Returns how much generic mana the spells with the specified types cost more for the player,
+/// or less if the value is negative.
+pub fn get_cost_modifier(game: &mut Game, player_id: ObjectId, types: &IndexSet<CardType>) -> i16 {
+    let applies = |spells: &Option<CardType>| spells.is_none_or(|spells| types.contains(&spells));
+
+    game.cards
+        .values()
+        .filter(|card| card.zone == Zone::Battlefield)
+        .flat_map(|card| {
+            let is_controller = card.owner_id == player_id;
+            card.static_abilities
+                .iter()
+                .map(move |ability| match ability {
+                    StaticAbility::CostReduction(spells, amount)
+                        if is_controller && applies(spells) =>
+                    {
+                        -(*amount as i16)
+                    }
+                    StaticAbility::OpponentCostIncrease(spells, amount)
+                        if !is_controller && applies(spells) =>
+                    {
+                        *amount as i16
+                    }
+                    _ => 0,
+                })
+        })
+        .sum()
+}
+
 pub fn can_play_card(game: &mut Game, card_id: ObjectId, player_id: ObjectId) -> bool {
     if let Some(priority) = &game.turn.priority {
         if priority.player_id != player_id {
@@ -233,7 +1199,11 @@ pub fn can_play_card(game: &mut Game, card_id: ObjectId, player_id: ObjectId) ->
         return false;
     }
 
-    let is_stack_empty = game.stack.len() == 0;
+    if is_split_second_on_stack(game) {
+        return false;
+    }
+
+    let is_stack_empty = game.stack.is_empty();
     let is_main_phase = game.turn.step.main();
     let is_active_player = game.turn.active_player == player_id;
     let lands_limit = if let Some(player) = game.get_player(player_id) {
@@ -242,29 +1212,81 @@ pub fn can_play_card(game: &mut Game, card_id: ObjectId, player_id: ObjectId) ->
         0
     };
 
-    if let Some(card) = game.get_card(card_id) {
-        return if card.owner_id != player_id {
+    let (types, abilities) = if let Some(card) = game.get_card(card_id) {
+        if card.owner_id != player_id {
             // Players can only play their own cards
             return false;
-        } else if card.kind == CardType::Instant {
-            // Instant spells can be played without time restrictions
-            true
+        }
+        (card.types.clone(), card.static_abilities.clone())
+    } else {
+        return false;
+    };
+
+    let sorcery_speed = is_stack_empty && is_main_phase && is_active_player;
+    if types.contains(&CardType::Land) {
+        // Lands are not cast, they can be played on the sorcery speed
+        // but not more than the player land limit per turn
+        return sorcery_speed && game.turn.lands_played < lands_limit;
+    }
+
+    if abilities.contains(&StaticAbility::CastOnlyDuringCombat) && !game.turn.step.combat() {
+        return false;
+    }
+    if abilities.contains(&StaticAbility::CastOnlyBeforeAttackers)
+        && game.turn.step >= Step::DeclareAttackers
+    {
+        return false;
+    }
+
+    if types.contains(&CardType::Instant)
+        || abilities.contains(&StaticAbility::Flash)
+        || has_flash_permission(game, player_id, &types)
+    {
+        // Instant spells can be played without time restrictions
+        true
+    } else {
+        // Other cards can be played on the sorcery speed:
+        // - stack must be empty;
+        // - must be in the main phase;
+        // - must be an active player;
+        sorcery_speed
+    }
+}
+
+/// Determines whether the player controls a permanent that allows them to cast
+/// spells with any of the specified types as though they had flash.
+fn has_flash_permission(game: &mut Game, player_id: ObjectId, types: &IndexSet<CardType>) -> bool {
+    let battlefield = if let Some(player) = game.get_player(player_id) {
+        player.battlefield.clone()
+    } else {
+        return false;
+    };
+
+    battlefield.iter().any(|card_id| {
+        if let Some(card) = game.cards.get(card_id) {
+            types.iter().any(|kind| {
+                card.static_abilities
+                    .contains(&StaticAbility::GrantFlash(*kind))
+            })
         } else {
-            // Other cards can be played on the sorcery speed:
-            // - stack must be empty;
-            // - must be in the main phase;
-            // - must be an active player;
-            let mut can_play = is_stack_empty && is_main_phase && is_active_player;
-            if card.kind == CardType::Land {
-                can_play &= game.turn.lands_played < lands_limit;
+            false
+        }
+    })
+}
+
+/// Determines whether there is a spell with split second on the stack.
+fn is_split_second_on_stack(game: &Game) -> bool {
+    game.stack.iter().any(|resolve| {
+        if let Spell(card_id) = resolve.kind {
+            if let Some(card) = game.cards.get(&card_id) {
+                return card.static_abilities.contains(&StaticAbility::SplitSecond);
             }
-            can_play
-        };
-    }
-    false
+        }
+        false
+    })
 }
 
-pub fn play_card(game: &mut Game, card_id: ObjectId, action: Action) {
+pub fn play_card(game: &mut Game, card_id: ObjectId, mut action: Action) {
     if !can_play_card(game, card_id, action.player_id) {
         return;
     }
@@ -278,7 +1300,7 @@ pub fn play_card(game: &mut Game, card_id: ObjectId, action: Action) {
     }
 
     if let Some(card) = game.get_card(card_id) {
-        if card.kind == CardType::Land {
+        if card.is_type(&CardType::Land) {
             // Lands don't use stack, must be played directly on the battlefield,
             // but not more than the player land limit per turn
             game.turn.lands_played += 1;
@@ -286,6 +1308,8 @@ pub fn play_card(game: &mut Game, card_id: ObjectId, action: Action) {
         } else {
             let effect = if let Some(play) = card.play_ability.clone() {
                 play.effect
+                    .choose_modes(&action.choices.modes)
+                    .kick(action.cast_costs.kicked())
             } else {
                 Effect::None
             };
@@ -309,11 +1333,11 @@ pub fn create_ability_action(
     card_id: ObjectId,
     ability_id: usize,
 ) -> Option<Action> {
-    let card = if let Some(card) = game.get_card(card_id) {
-        card
-    } else {
+    if !can_activate_ability(game, card_id, ability_id, player_id) {
         return None;
-    };
+    }
+
+    let card = game.get_card(card_id)?;
 
     match card.activated_abilities.get_mut(ability_id) {
         Some(ability) => {
@@ -329,7 +1353,104 @@ pub fn create_ability_action(
     }
 }
 
-pub fn play_ability(game: &mut Game, card_id: ObjectId, ability_id: usize, action: Action) -> bool {
+/// Determines whether the player can activate the ability of the permanent they control
+/// at this moment. Mana abilities can be activated without priority and while a spell
+/// with split second is on the stack.
+pub fn can_activate_ability(
+    game: &mut Game,
+    card_id: ObjectId,
+    ability_id: usize,
+    player_id: ObjectId,
+) -> bool {
+    let (ability, abilities) = match game.get_card(card_id) {
+        Some(card) if card.owner_id == player_id && card.zone == Zone::Battlefield => {
+            match card.activated_abilities.get(ability_id) {
+                Some(ability) => (ability.clone(), card.activated_abilities.clone()),
+                None => return false,
+            }
+        }
+        // Only the controller of a permanent can activate its abilities
+        _ => return false,
+    };
+
+    let has_priority = match &game.turn.priority {
+        Some(priority) => priority.player_id == player_id,
+        None => false,
+    };
+    if !ability.is_mana_ability() && !has_priority {
+        return false;
+    }
+
+    if !ability.is_mana_ability() && is_split_second_on_stack(game) {
+        // Only mana abilities can be activated while a spell with split second is on the stack
+        return false;
+    }
+
+    let is_active_player = game.turn.active_player == player_id;
+    let sorcery_speed =
+        has_priority && game.stack.is_empty() && game.turn.step.main() && is_active_player;
+
+    if ability.is_loyalty_ability() {
+        if !sorcery_speed {
+            return false;
+        }
+
+        if game
+            .turn
+            .activated_abilities
+            .iter()
+            .any(|(activated_id, activated_ability)| {
+                *activated_id == card_id && abilities[*activated_ability].is_loyalty_ability()
+            })
+        {
+            return false;
+        }
+    }
+
+    ability
+        .restrictions
+        .iter()
+        .all(|restriction| match restriction {
+            ActivationRestriction::SorcerySpeed => sorcery_speed,
+            ActivationRestriction::OncePerTurn => !game
+                .turn
+                .activated_abilities
+                .contains(&(card_id, ability_id)),
+            ActivationRestriction::Step(step) => is_active_player && game.turn.step == *step,
+            ActivationRestriction::Control(filter) => controls(game, player_id, card_id, filter),
+        })
+}
+
+/// Determines whether the player controls a permanent matching the filter of the source.
+fn controls(game: &mut Game, player_id: ObjectId, source_id: ObjectId, filter: &Filter) -> bool {
+    let battlefield = match game.get_player(player_id) {
+        Some(player) => player.battlefield.clone(),
+        None => return false,
+    };
+    battlefield
+        .iter()
+        .any(|card_id| filter.matches(game, *card_id, source_id, player_id))
+}
+
+/// Returns the permanents of all players matching the filter of the spell or ability.
+fn get_permanents(game: &mut Game, action: &Action, filter: &Filter) -> Vec<ObjectId> {
+    let permanents: Vec<ObjectId> = game
+        .players
+        .iter()
+        .flat_map(|player| player.battlefield.iter().cloned())
+        .collect();
+    permanents
+        .into_iter()
+        .filter(|card_id| filter.matches(game, *card_id, action.card_id, action.player_id))
+        .collect()
+}
+
+pub fn play_ability(
+    game: &mut Game,
+    card_id: ObjectId,
+    ability_id: usize,
+    mut action: Action,
+) -> bool {
     let card = if let Some(card) = game.get_card(card_id) {
         card
     } else {
@@ -342,8 +1463,13 @@ pub fn play_ability(game: &mut Game, card_id: ObjectId, ability_id: usize, actio
         return false;
     };
 
-    let player_id = card.owner_id;
+    // The player activating the ability controls it
+    let player_id = action.player_id;
+    if !can_activate_ability(game, card_id, ability_id, player_id) {
+        return false;
+    }
 
+    let is_mana_ability = ability.is_mana_ability();
     if !action.valid(game) {
         return false;
     }
@@ -351,6 +1477,7 @@ pub fn play_ability(game: &mut Game, card_id: ObjectId, ability_id: usize, actio
     if !action.pay(game) {
         return false;
     }
+    game.turn.activated_abilities.push((card_id, ability_id));
 
     let choice = action.choices.effect.clone();
     let effect = ability.effect.clone();
@@ -362,7 +1489,7 @@ pub fn play_ability(game: &mut Game, card_id: ObjectId, ability_id: usize, actio
     };
     game.stack.push(entry);
 
-    if let Effect::Mana(_) = ability.effect {
+    if is_mana_ability {
         // Mana abilities are resolved without stack.
         start_resolve(game);
         resolve_choice(
@@ -390,7 +1517,111 @@ pub fn start_resolve(game: &mut Game) {
     if game.stack.is_empty() {
         panic!("Stack is empty.");
     }
-    game.resolve = game.stack.pop();
+    let mut resolve = game.stack.pop();
+    if let Some(resolve) = &mut resolve {
+        let (legal, total) = resolve.action.count_legal_targets(game);
+        if total > 0 && legal == 0 {
+            // Spells and abilities with no legal targets left are countered on resolution
+            counter(game, resolve);
+        } else {
+            resolve.effect = prepare_next_effect(game, &resolve.effect, &resolve.action);
+        }
+    }
+    game.resolve = resolve;
+}
+
+/// Expands the effect applied to a group of players into the effects for each player
+/// in APNAP order. Returns None if the effect does not apply to a group of players.
+fn for_each_player(game: &mut Game, effect: &Effect, action: &Action) -> Option<Vec<Effect>> {
+    let (target, applied) = match effect {
+        Effect::ChosenMode(position, effect) => {
+            (action.get_mode_action(*position).required.target, &**effect)
+        }
+        effect => (action.required.target.clone(), effect),
+    };
+    if matches!(applied, Effect::None | Effect::ForPlayer(_, _)) {
+        return None;
+    }
+
+    let players: Vec<ObjectId> = match target {
+        Target::EachPlayer => game.get_apnap_player_ids(),
+        Target::EachOpponent => game
+            .get_apnap_player_ids()
+            .into_iter()
+            .filter(|player_id| *player_id != action.player_id)
+            .collect(),
+        _ => return None,
+    };
+    Some(
+        players
+            .into_iter()
+            .map(|player_id| {
+                let for_player = Effect::ForPlayer(player_id, Box::new(applied.clone()));
+                match effect {
+                    Effect::ChosenMode(position, _) => {
+                        Effect::ChosenMode(*position, Box::new(for_player))
+                    }
+                    _ => for_player,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Counters the spell or ability so it resolves without any effect,
+/// the countered spell is put into its owner's graveyard.
+fn counter(game: &mut Game, resolve: &mut Resolve) {
+    resolve.effect = Effect::None;
+    if let Spell(card_id) = resolve.kind {
+        if let Some(AlternativeCost::Flashback(_)) = resolve.action.cast_costs.alternative {
+            put_in_exile(game, card_id);
+        } else {
+            put_on_graveyard(game, card_id);
+        }
+    }
+}
+
+/// Evaluates the conditional effects and the amounts of the effect which resolves next
+/// against the current game state and flattens the combined effects, so the next effect
+/// can be resolved on its own.
+fn prepare_next_effect(game: &mut Game, effect: &Effect, action: &Action) -> Effect {
+    let mut effects = match effect {
+        Effect::And(effects) => effects.clone(),
+        effect => VecDeque::from([effect.clone()]),
+    };
+    while let Some(next) = effects.pop_front() {
+        match next {
+            Effect::If(condition, effect, otherwise) => {
+                if condition.check(game, action) {
+                    effects.push_front(*effect);
+                } else {
+                    effects.push_front(*otherwise);
+                }
+            }
+            Effect::And(combined) => {
+                for effect in combined.into_iter().rev() {
+                    effects.push_front(effect);
+                }
+            }
+            next => match for_each_player(game, &next, action) {
+                Some(expanded) => {
+                    for effect in expanded.into_iter().rev() {
+                        effects.push_front(effect);
+                    }
+                }
+                None => {
+                    effects.push_front(next.evaluate(game, action));
+                    break;
+                }
+            },
+        }
+    }
+
+    match effect {
+        Effect::And(_) => Effect::And(effects),
+        _ if effects.len() == 1 => effects.pop_front().unwrap_or_default(),
+        _ => Effect::And(effects),
+    }
 }
 
 /// Resolves the current effect with the specified choice and returns the next choice
@@ -406,14 +1637,23 @@ pub fn resolve_choice(
         Some(ref mut resolve) => {
             if let Effect::And(ref mut effects) = resolve.effect {
                 if let Some(effect) = effects.pop_front() {
-                    if let Err(err) = resolve_effect(game, &effect, &resolve.action, choice) {
-                        return Err(err);
-                    }
+                    resolve_effect(game, &effect, &resolve.action, choice)?;
                 }
-                if effects.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(get_next_resolve_choice(resolve))
+                resolve.effect = prepare_next_effect(game, &resolve.effect, &resolve.action);
+                let next = match &resolve.effect {
+                    Effect::And(effects) => effects.front().cloned(),
+                    _ => None,
+                };
+                match next {
+                    Some(effect) => Ok(Some(get_next_resolve_choice(resolve).unwrap_or(
+                        // Effects without choices are resolved automatically
+                        ResolveChoice {
+                            player_id: resolve.player_id,
+                            effect,
+                            ..Default::default()
+                        },
+                    ))),
+                    None => Ok(None),
                 }
             } else {
                 resolve_effect(game, &resolve.effect, &resolve.action, choice)
@@ -437,17 +1677,50 @@ pub fn resolve_choice(
 pub fn end_resolve(game: &mut Game) {
     let resolve = game.resolve.clone().unwrap();
     if let Spell(card_id) = resolve.kind {
-        if let Some(card) = game.get_card(card_id) {
-            match &card.kind {
-                CardType::Artifact
-                | CardType::Enchantment
-                | CardType::Creature
-                | CardType::Land => put_on_battlefield(game, card_id),
-                CardType::Instant | CardType::Sorcery => put_on_graveyard(game, card_id),
+        // Countered spells have already left the stack
+        if let Some(card) = game
+            .get_card(card_id)
+            .filter(|card| card.zone == Zone::Stack)
+        {
+            let costs = &resolve.action.cast_costs;
+            if card.is_permanent() {
+                let is_creature = card.is_creature();
+                let is_aura = card.subtypes.contains(&CardSubtype::Aura);
+                let enchanted_id = match resolve.action.choices.target {
+                    Choice::Card(target_id) if is_aura => Some(target_id),
+                    _ => None,
+                };
+
+                if is_aura
+                    && !enchanted_id.is_some_and(|target_id| can_attach(game, card_id, target_id))
+                {
+                    // Auras with an illegal target are put into the graveyard as they resolve
+                    put_on_graveyard(game, card_id);
+                } else {
+                    put_on_battlefield(game, card_id);
+                    if let Some(target_id) = enchanted_id {
+                        attach(game, card_id, target_id);
+                    }
+                }
+
+                if is_creature {
+                    if let Some(AlternativeCost::Evoke(_)) = costs.alternative {
+                        // Evoked creatures are sacrificed as they enter the battlefield
+                        put_on_graveyard(game, card_id);
+                    }
+                }
+            } else if let Some(AlternativeCost::Flashback(_)) = costs.alternative {
+                put_in_exile(game, card_id);
+            } else if costs.has_buyback() {
+                put_in_hand(game, card_id);
+            } else {
+                put_on_graveyard(game, card_id);
             }
         }
     }
 
+    check_state_based_actions(game);
+
     game.resolve = None;
     game.turn.priority = Some(Priority::new(game.turn.active_player));
 }
@@ -462,33 +1735,169 @@ fn resolve_effect(
         return Err(ResolveError::InvalidChoice);
     }
 
+    if matches!(action.required.target, Target::Slots(_))
+        && !matches!(effect, Effect::DividedDamage(_))
+    {
+        // The effect is applied to each chosen target in turn
+        for (target, choice) in action.get_targets() {
+            resolve_effect(game, effect, &action.with_target(target, choice), r.clone())?;
+        }
+        return Ok(None);
+    }
+
+    if effect.uses_target()
+        && action.required.target.is_targeted()
+        && !action.has_legal_target(game)
+    {
+        // Parts of the effect with an illegal target do nothing
+        return Ok(None);
+    }
+
     if let Some(owner) = game.get_player(action.player_id) {
         match effect {
+            // Spells can have no effect, e.g. when the kicked effects were not kicked
+            Effect::None => {}
+            Effect::ChosenMode(position, effect) => {
+                return resolve_effect(game, effect, &action.get_mode_action(*position), r);
+            }
+            Effect::ForPlayer(player_id, effect) => {
+                return resolve_effect(game, effect, &action.for_player(*player_id), r);
+            }
             Effect::Mana(mana) => {
                 if mana.has(&Color::Any) {
                     if let Choice::Mana(mana) = r.choice {
-                        owner.mana += mana;
+                        owner.mana.add_entry(ManaEntry {
+                            source_id: Some(action.card_id),
+                            ..ManaEntry::new(mana)
+                        });
                     } else {
                         return Err(ResolveError::InvalidChoice);
                     }
                 } else {
-                    owner.mana += *mana;
+                    owner.mana.add_entry(ManaEntry {
+                        source_id: Some(action.card_id),
+                        ..ManaEntry::new(*mana)
+                    });
                 }
             }
             Effect::Damage(damage) => match action.choices.target {
                 Choice::Player(player_id) => {
-                    deal_player_damage(game, player_id, *damage);
+                    deal_player_damage(game, action.card_id, player_id, damage.resolved()?);
                 }
                 Choice::Card(card_id) => {
-                    deal_damage(game, card_id, *damage);
+                    deal_damage(game, action.card_id, card_id, damage.resolved()?);
+                }
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::DividedDamage(_) => {
+                let targets = action.get_targets();
+                for ((target, choice), damage) in targets.iter().zip(action.choices.division.iter())
+                {
+                    let target_action = action.with_target(target.clone(), choice.clone());
+                    if !target_action.has_legal_target(game) {
+                        continue;
+                    }
+                    match choice {
+                        Choice::Player(player_id) => {
+                            deal_player_damage(game, action.card_id, *player_id, *damage);
+                        }
+                        Choice::Card(card_id) => {
+                            deal_damage(game, action.card_id, *card_id, *damage);
+                        }
+                        _ => return Err(ResolveError::InvalidTarget),
+                    }
+                }
+            }
+            Effect::GainLife(life) => gain_life(game, action.player_id, life.resolved()?),
+            Effect::LoseLife(life) => match action.choices.target {
+                Choice::Player(player_id) => lose_life(game, player_id, life.resolved()?),
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::SetLife(life) => match action.choices.target {
+                Choice::Player(player_id) => {
+                    if let Some(player) = game.get_player(player_id) {
+                        player.life = *life;
+                    }
+                    check_life(game, player_id);
+                }
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::Pump(power, toughness) => match action.choices.target {
+                Choice::Card(card_id) => match game.get_card(card_id) {
+                    Some(card) if card.zone == Zone::Battlefield && card.is_creature() => {
+                        // The bonus lasts until the creature is restored in the cleanup step
+                        card.state.power.current += power;
+                        card.state.toughness.current += toughness;
+                    }
+                    _ => {
+                        return Err(ResolveError::InvalidTarget);
+                    }
+                },
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::Mill(_)
+            | Effect::Scry(_)
+            | Effect::Surveil(_)
+            | Effect::Search(_, _)
+            | Effect::RevealTop(_) => match action.choices.target {
+                Choice::Player(player_id) => {
+                    resolve_library_effect(game, effect, action, player_id, r)?
                 }
                 _ => {
                     return Err(ResolveError::InvalidTarget);
                 }
             },
+            Effect::Destroy
+            | Effect::Exile
+            | Effect::ReturnToHand
+            | Effect::PutOnLibrary
+            | Effect::Tap
+            | Effect::Untap => match action.choices.target {
+                Choice::Card(card_id) => affect_permanent(game, effect, action.card_id, card_id)?,
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::All(filter, effect) => {
+                for card_id in get_permanents(game, action, filter) {
+                    affect_permanent(game, effect, action.card_id, card_id)?;
+                }
+            }
+            Effect::Attach => match action.choices.target {
+                Choice::Card(target_id) => {
+                    if !attach(game, action.card_id, target_id) {
+                        return Err(ResolveError::InvalidTarget);
+                    }
+                }
+                _ => {
+                    return Err(ResolveError::InvalidTarget);
+                }
+            },
+            Effect::CreateToken(token, count) => {
+                let (controller_id, copied_id) = match action.choices.target {
+                    Choice::Player(player_id) => (player_id, None),
+                    Choice::Card(card_id) => (action.player_id, Some(card_id)),
+                    _ => (action.player_id, None),
+                };
+
+                for _ in 0..*count {
+                    let card = token
+                        .get_card(game, controller_id, copied_id)
+                        .ok_or(ResolveError::InvalidTarget)?;
+                    create_token(game, card);
+                }
+            }
             Effect::Discard(count) => match r.choice {
                 Choice::And(choices) => {
-                    if choices.len() != *count {
+                    if choices.len() != count.resolved()? as usize {
                         return Err(ResolveError::InvalidChoice);
                     }
 
@@ -505,23 +1914,16 @@ fn resolve_effect(
                     return Err(ResolveError::InvalidChoice);
                 }
             },
-            Effect::Draw(count) => match action.required.target {
-                Target::Owner => {
-                    for _ in 1..=*count {
-                        draw_card(game, action.player_id);
-                    }
-                }
-                Target::Player => {
-                    if let Choice::Player(player_id) = action.choices.target {
-                        for _ in 1..=*count {
-                            draw_card(game, player_id);
-                        }
-                    }
-                }
-                _ => {
-                    return Err(ResolveError::InvalidTarget);
+            Effect::Draw(count) => {
+                let player_id = match action.choices.target {
+                    Choice::Player(player_id) => player_id,
+                    _ if action.required.target == Target::Owner => action.player_id,
+                    _ => return Err(ResolveError::InvalidTarget),
+                };
+                for _ in 1..=count.resolved()? {
+                    draw_card(game, player_id);
                 }
-            },
+            }
             _ => {
                 return Err(ResolveError::UnknownEffect);
             }
@@ -531,28 +1933,172 @@ fn resolve_effect(
     Err(ResolveError::UnknownActionOwner)
 }
 
-// TODO: Simplify this signature
-pub fn get_next_resolve_choice(resolve: &Resolve) -> Option<ResolveChoice> {
-    let effect = match &resolve.effect {
-        Effect::And(effects) => effects.front().unwrap_or(&Effect::None),
-        effect => effect,
-    };
-
-    match effect {
-        Effect::Mana(mana) => {
+/// Returns the number of cards from the top of the player's library, starting with the top card.
+pub fn get_library_top(game: &mut Game, player_id: ObjectId, count: usize) -> Vec<ObjectId> {
+    if let Some(player) = game.get_player(player_id) {
+        player.library.iter().rev().take(count).cloned().collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Resolves the effect on the player's library with the choice of the player.
+fn resolve_library_effect(
+    game: &mut Game,
+    effect: &Effect,
+    action: &Action,
+    player_id: ObjectId,
+    r: ResolveChoice,
+) -> Result<(), ResolveError> {
+    match effect {
+        Effect::Mill(count) => {
+            for card_id in get_library_top(game, player_id, count.resolved()? as usize) {
+                put_on_graveyard(game, card_id);
+            }
+        }
+        Effect::Scry(count) | Effect::Surveil(count) => {
+            let (top, bottom, graveyard) = match r.choice {
+                Choice::Arrange {
+                    top,
+                    bottom,
+                    graveyard,
+                } => (top, bottom, graveyard),
+                _ => return Err(ResolveError::InvalidChoice),
+            };
+
+            let is_scry = matches!(effect, Effect::Scry(_));
+            if (is_scry && !graveyard.is_empty()) || (!is_scry && !bottom.is_empty()) {
+                return Err(ResolveError::InvalidChoice);
+            }
+
+            // Every card looked at must be put exactly once
+            let mut looked = get_library_top(game, player_id, *count);
+            let mut arranged: Vec<ObjectId> = top
+                .iter()
+                .chain(bottom.iter())
+                .chain(graveyard.iter())
+                .cloned()
+                .collect();
+            looked.sort();
+            arranged.sort();
+            if looked != arranged {
+                return Err(ResolveError::InvalidChoice);
+            }
+
+            for card_id in top.into_iter().rev() {
+                put_on_deck_top(game, card_id, player_id);
+            }
+            for card_id in bottom {
+                put_on_deck_bottom(game, card_id, player_id);
+            }
+            for card_id in graveyard {
+                put_on_graveyard(game, card_id);
+            }
+        }
+        Effect::Search(filter, zone) => {
+            match r.choice {
+                Choice::Card(card_id) => {
+                    let found =
+                        match game.get_card(card_id) {
+                            Some(card) => card.owner_id == player_id && card.zone == Zone::Library,
+                            None => false,
+                        } && filter.matches(game, card_id, action.card_id, action.player_id);
+                    if !found {
+                        return Err(ResolveError::InvalidChoice);
+                    }
+
+                    match zone {
+                        Zone::Hand => put_in_hand(game, card_id),
+                        Zone::Battlefield => put_on_battlefield(game, card_id),
+                        Zone::Graveyard => put_on_graveyard(game, card_id),
+                        _ => return Err(ResolveError::UnknownEffect),
+                    }
+                }
+                // Players may fail to find a card in a hidden zone
+                Choice::None => {}
+                _ => return Err(ResolveError::InvalidChoice),
+            }
+            shuffle_deck(game, player_id);
+        }
+        Effect::RevealTop(count) => {
+            let revealed = get_library_top(game, player_id, *count);
+            let chosen = match r.choice {
+                Choice::Card(card_id) if revealed.contains(&card_id) => card_id,
+                Choice::None if revealed.is_empty() => return Ok(()),
+                _ => return Err(ResolveError::InvalidChoice),
+            };
+
+            put_in_hand(game, chosen);
+            for card_id in revealed {
+                if card_id != chosen {
+                    put_on_deck_bottom(game, card_id, player_id);
+                }
+            }
+        }
+        _ => return Err(ResolveError::UnknownEffect),
+    }
+    Ok(())
+}
+
+/// Applies the effect of the source to the permanent, moving it to another zone
+/// or changing its state.
+fn affect_permanent(
+    game: &mut Game,
+    effect: &Effect,
+    source_id: ObjectId,
+    card_id: ObjectId,
+) -> Result<(), ResolveError> {
+    let owner_id = match game.get_card(card_id) {
+        Some(card) if card.zone == Zone::Battlefield => card.owner_id,
+        _ => return Err(ResolveError::InvalidTarget),
+    };
+
+    match effect {
+        Effect::Damage(damage) => {
+            deal_damage(game, source_id, card_id, damage.resolved()?);
+        }
+        Effect::Destroy => {
+            destroy_card(game, card_id);
+        }
+        Effect::Exile => put_in_exile(game, card_id),
+        Effect::ReturnToHand => put_in_hand(game, card_id),
+        Effect::PutOnLibrary => put_on_deck_top(game, card_id, owner_id),
+        Effect::Tap => {
+            tap_card(game, card_id, Some(source_id));
+        }
+        Effect::Untap => {
+            untap_card(game, card_id, Some(source_id));
+        }
+        _ => return Err(ResolveError::UnknownEffect),
+    }
+    Ok(())
+}
+
+// TODO: Simplify this signature
+pub fn get_next_resolve_choice(resolve: &Resolve) -> Option<ResolveChoice> {
+    let effect = match &resolve.effect {
+        Effect::And(effects) => effects.front().unwrap_or(&Effect::None),
+        effect => effect,
+    };
+
+    match effect {
+        Effect::Mana(mana) => {
             if mana.has(&Color::Any) {
-                let mut r = ResolveChoice::default();
-                r.effect = effect.clone();
-                r.player_id = resolve.player_id;
-                Some(r)
+                Some(ResolveChoice {
+                    effect: effect.clone(),
+                    player_id: resolve.player_id,
+                    ..Default::default()
+                })
             } else {
                 None
             }
         }
-        Effect::Discard(_) => {
-            let mut r = ResolveChoice::default();
-            r.effect = effect.clone();
-            r.player_id = match resolve.action.choices.target {
+        Effect::Discard(_)
+        | Effect::Scry(_)
+        | Effect::Surveil(_)
+        | Effect::Search(_, _)
+        | Effect::RevealTop(_) => {
+            let player_id = match resolve.action.choices.target {
                 Choice::Player(player_id) => player_id,
                 _ => {
                     if resolve.action.required.target == Target::Owner {
@@ -562,54 +2108,365 @@ pub fn get_next_resolve_choice(resolve: &Resolve) -> Option<ResolveChoice> {
                     }
                 }
             };
-            Some(r)
+            Some(ResolveChoice {
+                effect: effect.clone(),
+                player_id,
+                ..Default::default()
+            })
         }
+        Effect::ChosenMode(position, effect) => get_next_resolve_choice(&Resolve {
+            action: resolve.action.get_mode_action(*position),
+            effect: *effect.clone(),
+            ..resolve.clone()
+        }),
+        Effect::ForPlayer(player_id, effect) => get_next_resolve_choice(&Resolve {
+            action: resolve.action.for_player(*player_id),
+            effect: *effect.clone(),
+            ..resolve.clone()
+        }),
         _ => None,
     }
 }
 
-pub(crate) fn deal_player_damage(game: &mut Game, player_id: ObjectId, damage: u16) {
+/// Deals damage from the source to the player applying abilities of the source
+/// which modify damage. Returns the amount of damage dealt.
+pub(crate) fn deal_player_damage(
+    game: &mut Game,
+    source_id: ObjectId,
+    player_id: ObjectId,
+    damage: u16,
+) -> u16 {
+    if damage == 0 {
+        return 0;
+    }
+
+    let source_abilities = get_static_abilities(game, source_id);
+    if let Some(player) = game.get_player(player_id) {
+        if source_abilities.contains(&StaticAbility::Infect) {
+            // Infect damage is dealt to players in the form of poison counters
+            player.poison += damage;
+            if player.poison >= POISON_LIMIT {
+                game.status = GameStatus::Lose(player_id);
+            }
+        } else {
+            player.life -= damage as i16;
+            if player.life <= 0 {
+                game.status = GameStatus::Lose(player_id);
+            }
+        }
+    } else {
+        return 0;
+    }
+
+    apply_lifelink(game, source_id, &source_abilities, damage);
+    damage
+}
+
+/// Deals damage from the source to the creature or planeswalker applying abilities
+/// of the source which modify damage. Returns the amount of damage dealt.
+///
+/// Creatures with lethal damage are not destroyed until state-based actions are checked.
+pub(crate) fn deal_damage(
+    game: &mut Game,
+    source_id: ObjectId,
+    card_id: ObjectId,
+    damage: u16,
+) -> u16 {
     if damage == 0 {
+        return 0;
+    }
+
+    if is_protected_from(game, card_id, source_id) {
+        // Damage from sources the creature has protection from is prevented
+        return 0;
+    }
+
+    let source_abilities = get_static_abilities(game, source_id);
+    if let Some(card) = game.get_card(card_id) {
+        if card.zone != Zone::Battlefield {
+            return 0;
+        }
+
+        if card.is_type(&CardType::Planeswalker) {
+            // Damage dealt to planeswalkers removes that many loyalty counters
+            let loyalty = card.state.get_counter(Counter::Loyalty);
+            card.state
+                .remove_counters(Counter::Loyalty, cmp::min(loyalty, damage));
+            apply_lifelink(game, source_id, &source_abilities, damage);
+            return damage;
+        }
+
+        if !card.is_creature() {
+            return 0;
+        }
+
+        if source_abilities.contains(&StaticAbility::Infect)
+            || source_abilities.contains(&StaticAbility::Wither)
+        {
+            // Infect and wither damage is dealt to creatures in the form of -1/-1 counters
+            card.state.add_counters(Counter::Minus, damage);
+        } else {
+            card.state.mark_damage(damage as i16);
+        }
+
+        if source_abilities.contains(&StaticAbility::Deathtouch) {
+            // Any amount of damage dealt by deathtouch sources is lethal
+            let toughness = card.state.toughness.current;
+            if toughness > 0 {
+                card.state.mark_damage(toughness);
+            }
+        }
+    } else {
+        return 0;
+    }
+
+    apply_lifelink(game, source_id, &source_abilities, damage);
+    damage
+}
+
+fn apply_lifelink(
+    game: &mut Game,
+    source_id: ObjectId,
+    source_abilities: &IndexSet<StaticAbility>,
+    damage: u16,
+) {
+    if !source_abilities.contains(&StaticAbility::Lifelink) {
+        return;
+    }
+
+    let controller_id = if let Some(source) = game.get_card(source_id) {
+        source.owner_id
+    } else {
         return;
+    };
+    gain_life(game, controller_id, damage);
+}
+
+pub(crate) fn gain_life(game: &mut Game, player_id: ObjectId, amount: u16) {
+    if let Some(player) = game.get_player(player_id) {
+        player.life += amount as i16;
+        *game.turn.life_gained.entry(player_id).or_default() += amount;
+    }
+}
+
+/// Reduces the life of the player without dealing damage, so it cannot be prevented
+/// or modified by abilities of a source.
+pub(crate) fn lose_life(game: &mut Game, player_id: ObjectId, amount: u16) {
+    if let Some(player) = game.get_player(player_id) {
+        player.life -= amount as i16;
     }
+    check_life(game, player_id);
+}
 
+fn check_life(game: &mut Game, player_id: ObjectId) {
     if let Some(player) = game.get_player(player_id) {
-        player.life -= damage as i16;
         if player.life <= 0 {
             game.status = GameStatus::Lose(player_id);
         }
     }
 }
 
-pub(crate) fn deal_damage(game: &mut Game, card_id: ObjectId, damage: u16) {
-    if damage == 0 {
-        return;
+fn get_static_abilities(game: &mut Game, card_id: ObjectId) -> IndexSet<StaticAbility> {
+    if let Some(card) = game.get_card(card_id) {
+        card.static_abilities.clone()
+    } else {
+        IndexSet::new()
     }
+}
 
+pub fn apply_static_abilities(game: &mut Game, card_id: ObjectId) {
     if let Some(card) = game.get_card(card_id) {
-        match &mut card.kind {
-            CardType::Creature => {
-                card.state.toughness.current -= damage as i16;
-                if card.state.toughness.current <= 0 {
-                    put_on_graveyard(game, card_id);
-                }
+        for ability in card.static_abilities.iter() {
+            if ability == &StaticAbility::Haste {
+                card.state.summoning_sickness = Value::new(false);
             }
-            _ => {}
         }
     }
 }
 
-pub fn apply_static_abilities(game: &mut Game, card_id: ObjectId) {
+/// Determines whether the aura or equipment can be attached to the permanent.
+/// Equipment can be attached only to creatures controlled by its controller.
+pub fn can_attach(game: &mut Game, card_id: ObjectId, target_id: ObjectId) -> bool {
+    let (controller_id, is_equipment) = if let Some(card) = game.get_card(card_id) {
+        (
+            card.owner_id,
+            card.subtypes.contains(&CardSubtype::Equipment),
+        )
+    } else {
+        return false;
+    };
+
+    if card_id == target_id || is_protected_from(game, target_id, card_id) {
+        // Permanents cannot be enchanted or equipped by anything they have protection from
+        return false;
+    }
+
+    if let Some(target) = game.get_card(target_id) {
+        return target.zone == Zone::Battlefield
+            && target.is_creature()
+            && (!is_equipment || target.owner_id == controller_id);
+    }
+    false
+}
+
+/// Attaches the aura or equipment on the battlefield to the permanent.
+/// Returns false if it cannot be attached.
+pub fn attach(game: &mut Game, card_id: ObjectId, target_id: ObjectId) -> bool {
+    if !can_attach(game, card_id, target_id) {
+        return false;
+    }
+
     if let Some(card) = game.get_card(card_id) {
+        if card.zone != Zone::Battlefield {
+            return false;
+        }
+        card.state.attached_to = Some(target_id);
+    }
+    apply_attachments(game);
+    true
+}
+
+/// Breaks the illegal attachments. Auras which are not attached to a legal permanent
+/// are put into the graveyard, while equipment stays on the battlefield unattached.
+/// Returns whether any attachment was broken.
+pub(crate) fn check_attachments(game: &mut Game) -> bool {
+    let attachments: Vec<(ObjectId, Option<ObjectId>, bool)> = game
+        .cards
+        .values()
+        .filter(|card| card.zone == Zone::Battlefield)
+        .filter(|card| {
+            card.state.attached_to.is_some() || card.subtypes.contains(&CardSubtype::Aura)
+        })
+        .map(|card| {
+            let is_aura = card.subtypes.contains(&CardSubtype::Aura);
+            (card.id, card.state.attached_to, is_aura)
+        })
+        .collect();
+
+    let mut changed = false;
+    for (card_id, target_id, is_aura) in attachments {
+        let legal = target_id.is_some_and(|target_id| can_attach(game, card_id, target_id));
+        if legal {
+            continue;
+        }
+
+        if is_aura {
+            put_on_graveyard(game, card_id);
+        } else if let Some(card) = game.get_card(card_id) {
+            card.state.attached_to = None;
+        }
+        changed = true;
+    }
+    apply_attachments(game);
+    changed
+}
+
+/// Applies the static bonuses of the auras and equipment to the permanents they are attached to.
+fn apply_attachments(game: &mut Game) {
+    let mut bonuses: HashMap<ObjectId, ((i16, i16), IndexSet<StaticAbility>)> = HashMap::new();
+    for card in game.cards.values() {
+        let target_id = match card.state.attached_to {
+            Some(target_id) if card.zone == Zone::Battlefield => target_id,
+            _ => continue,
+        };
+
+        let (modifier, abilities) = bonuses.entry(target_id).or_default();
         for ability in card.static_abilities.iter() {
             match ability {
-                StaticAbility::Haste => {
-                    card.state.summoning_sickness = Value::new(false);
+                StaticAbility::AttachedModifier(power, toughness) => {
+                    modifier.0 += power;
+                    modifier.1 += toughness;
+                }
+                StaticAbility::AttachedAbility(ability) => {
+                    abilities.insert(**ability);
                 }
                 _ => {}
             }
         }
     }
+
+    let battlefield: Vec<ObjectId> = game
+        .cards
+        .values()
+        .filter(|card| card.zone == Zone::Battlefield)
+        .map(|card| card.id)
+        .collect();
+    for card_id in battlefield {
+        let (modifier, abilities) = bonuses.remove(&card_id).unwrap_or_default();
+        if let Some(card) = game.get_card(card_id) {
+            card.state.set_modifier(modifier);
+            card.set_granted_abilities(abilities);
+        }
+    }
+}
+
+/// Determines whether the card can be targeted by spells or abilities of the source
+/// controlled by the specified player.
+pub fn can_be_targeted(
+    game: &mut Game,
+    card_id: ObjectId,
+    source_id: ObjectId,
+    player_id: ObjectId,
+) -> bool {
+    if is_protected_from(game, card_id, source_id) {
+        return false;
+    }
+
+    if let Some(card) = game.get_card(card_id) {
+        if card.static_abilities.contains(&StaticAbility::Shroud) {
+            return false;
+        }
+        if card.owner_id != player_id && card.static_abilities.contains(&StaticAbility::Hexproof) {
+            return false;
+        }
+        return true;
+    }
+    false
+}
+
+/// Determines whether the card has protection from the source.
+pub fn is_protected_from(game: &mut Game, card_id: ObjectId, source_id: ObjectId) -> bool {
+    let (source_colors, source_types, source_subtypes) =
+        if let Some(source) = game.get_card(source_id) {
+            (
+                source.colors(),
+                source.types.clone(),
+                source.subtypes.clone(),
+            )
+        } else {
+            return false;
+        };
+
+    if let Some(card) = game.get_card(card_id) {
+        return card.static_abilities.iter().any(|ability| match ability {
+            StaticAbility::Protection(Quality::Color(color)) => source_colors.contains(color),
+            StaticAbility::Protection(Quality::Type(kind)) => source_types.contains(kind),
+            StaticAbility::Protection(Quality::Subtype(subtype)) => {
+                source_subtypes.contains(subtype)
+            }
+            _ => false,
+        });
+    }
+    false
+}
+
+/// Returns the ward costs that the player must pay to target the card.
+/// Ward costs are paid along with the other costs of the spell or ability
+/// instead of on resolution of the ward trigger.
+pub fn get_ward_costs(game: &mut Game, card_id: ObjectId, player_id: ObjectId) -> Vec<Cost> {
+    if let Some(card) = game.get_card(card_id) {
+        if card.zone == Zone::Battlefield && card.owner_id != player_id {
+            return card
+                .static_abilities
+                .iter()
+                .filter_map(|ability| match ability {
+                    StaticAbility::Ward(cost) => Some((*cost).clone()),
+                    _ => None,
+                })
+                .collect();
+        }
+    }
+    vec![]
 }
 
 #[cfg(test)]
@@ -618,30 +2475,128 @@ mod tests {
     use std::collections::VecDeque;
 
     use crate::abilities::{
-        end_resolve, get_next_resolve_choice, resolve_choice, start_resolve, ResolveChoice,
-        ResolveError,
+        attach, deal_damage, end_resolve, get_next_resolve_choice, resolve_choice, resolve_effect,
+        start_resolve, Amount, Comparison, Controller, EffectCondition, Filter, Mode, Quality,
+        ResolveChoice, ResolveError, TargetSlot, Token,
     };
     use crate::card::{put_on_deck_top, put_on_graveyard};
     use crate::{
         abilities::{
-            can_play_card, create_ability_action, create_card_action, play_ability, play_card,
-            resolve_auto, ActivatedAbility, Condition, Cost, Effect, PlayAbility, StaticAbility,
-            Target, TriggeredAbility,
+            can_activate_ability, can_play_card, create_ability_action, create_card_action,
+            create_card_action_with_costs, play_ability, play_card, resolve_auto, ActivatedAbility,
+            ActivationRestriction, AdditionalCost, AlternativeCost, CastCosts, Condition, Cost,
+            Effect, PlayAbility, StaticAbility, Target, TriggeredAbility,
         },
         action::{Action, Choice},
-        card::{put_in_hand, put_on_battlefield, put_on_deck_bottom, Card, CardSubtype, Zone},
-        game::{add_mana, Game, ObjectId},
-        mana::Mana,
+        card::{
+            put_in_exile, put_in_hand, put_on_battlefield, put_on_deck_bottom, Card, CardSubtype,
+            CardType, Counter, Zone,
+        },
+        game::{add_mana, check_state_based_actions, Game, GameStatus, ObjectId, Player},
+        mana::{Color, Mana, ManaEntry, ManaRestriction, ManaSymbol, ManaUsage},
         turn::{
             assign_combat_damage, can_declare_attacker, can_declare_blocker, cleanup_step,
-            combat_damage_step_end, combat_damage_step_start, declare_attacker,
+            combat_begin_step, combat_damage_step_end, combat_damage_step_start, declare_attacker,
             declare_attackers_step_end, declare_attackers_step_start, declare_blocker,
             declare_blockers_step_end, declare_blockers_step_start, fast_combat,
             fast_declare_attacker, fast_declare_blockers, pass_priority, postcombat_step,
-            precombat_step, reset_combat_assignments, upkeep_step, AttackType,
+            precombat_step, reset_combat_assignments, upkeep_step, AttackType, Priority, Step,
         },
     };
 
+    fn create_spell(
+        game: &mut Game,
+        mut card: Card,
+        cost: &'static str,
+        effect: Effect,
+        target: Target,
+    ) -> ObjectId {
+        card.cost = Cost::Mana(cost);
+        card.play_ability = Some(PlayAbility { effect, target });
+        let card_id = game.add_card(card);
+        put_in_hand(game, card_id);
+        card_id
+    }
+
+    fn create_burn(game: &mut Game, player_id: ObjectId, effect: Effect) -> ObjectId {
+        let card = Card::new_sorcery(player_id);
+        create_spell(game, card, "1R", effect, Target::Player)
+    }
+
+    fn create_shock(game: &mut Game, player_id: ObjectId) -> ObjectId {
+        let card = Card::new_instant(player_id);
+        let effect = Effect::Damage(Amount::Fixed(2));
+        let target = Target::AnyOf(&[Target::Player, Target::Creature]);
+        create_spell(game, card, "R", effect, target)
+    }
+
+    fn cast_sorcery(
+        game: &mut Game,
+        player_id: ObjectId,
+        effect: Effect,
+        target: Target,
+        choice: Choice,
+    ) {
+        play_sorcery(game, player_id, effect, target, choice);
+        resolve_auto(game);
+    }
+
+    fn play_sorcery(
+        game: &mut Game,
+        player_id: ObjectId,
+        effect: Effect,
+        target: Target,
+        choice: Choice,
+    ) {
+        let card_id = create_sorcery(game, player_id, effect, target);
+        let action = create_sorcery_action(game, card_id, player_id, choice);
+        play_card(game, card_id, action);
+    }
+
+    fn create_sorcery(
+        game: &mut Game,
+        player_id: ObjectId,
+        effect: Effect,
+        target: Target,
+    ) -> ObjectId {
+        let card_id = create_spell(game, Card::new_sorcery(player_id), "W", effect, target);
+        precombat_step(game);
+        add_mana(game, player_id, Mana::from("W"));
+        card_id
+    }
+
+    fn create_sorcery_action(
+        game: &mut Game,
+        card_id: ObjectId,
+        player_id: ObjectId,
+        choice: Choice,
+    ) -> Action {
+        let mut action = create_card_action(game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("W"));
+        action.choices.target = choice;
+        action
+    }
+
+    fn create_library(game: &mut Game, player_id: ObjectId, count: usize) -> Vec<ObjectId> {
+        // Returns the cards starting with the top card of the library
+        let mut library = vec![];
+        for _ in 0..count {
+            let card_id = game.add_card(Card::new_land(player_id));
+            put_on_deck_top(game, card_id, player_id);
+            library.insert(0, card_id);
+        }
+        library
+    }
+
+    fn resolve_with_choice(game: &mut Game, choice: Choice) -> Result<(), ResolveError> {
+        start_resolve(game);
+        let mut next_choice = get_next_resolve_choice(&game.resolve.clone().unwrap()).unwrap();
+        next_choice.choice = choice;
+        let result = resolve_choice(game, next_choice).map(|_| ());
+        end_resolve(game);
+        result
+    }
+
     #[test]
     fn test_mana_ability() {
         let (mut game, player_id, _) = Game::new();
@@ -654,6 +2609,7 @@ mod tests {
             cost: Cost::Tap(Target::Source),
             effect: Effect::Mana(Mana::from("G")),
             target: Target::None,
+            restrictions: vec![],
         });
         let card_id = game.add_card(card);
 
@@ -663,7 +2619,7 @@ mod tests {
         play_ability(&mut game, card_id, 0, action);
 
         let player = game.get_player(player_id).unwrap();
-        assert_eq!(player.mana.green, 1);
+        assert_eq!(player.mana.total().green, 1);
 
         let card = game.get_card(card_id).unwrap();
         assert!(card.state.tapped.current);
@@ -680,12 +2636,13 @@ mod tests {
                 cost: Cost::Tap(Target::Source),
                 effect: Effect::Mana(Mana::from("*")),
                 target: Target::None,
+                restrictions: vec![],
             }
         });
         card.triggered_abilities.push({
             TriggeredAbility {
                 condition: Condition::Tap(Target::Source),
-                effect: Effect::Damage(1),
+                effect: Effect::Damage(Amount::Fixed(1)),
                 target: Target::Owner,
             }
         });
@@ -701,24 +2658,26 @@ mod tests {
         resolve_auto(&mut game);
 
         let player = game.get_player(player_id).unwrap();
-        assert_eq!(player.mana.black, 1);
+        assert_eq!(player.mana.total().black, 1);
         assert_eq!(player.life, 19);
     }
 
     #[test]
     fn test_activate_damage_ability_for_mana() {
         let (mut game, player_id, opponent_id) = Game::new();
-        add_mana(&mut game, player_id, Mana::from("R"));
 
         let mut card = Card::new_artifact(player_id);
         card.activated_abilities.push(ActivatedAbility {
             cost: Cost::Mana("R"),
-            effect: Effect::Damage(1),
+            effect: Effect::Damage(Amount::Fixed(1)),
             target: Target::Player,
+            restrictions: vec![],
         });
         let card_id = game.add_card(card);
 
         put_on_battlefield(&mut game, card_id);
+        upkeep_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("R"));
 
         let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
         action.choices.cost = Choice::Mana(Mana::from("R"));
@@ -731,7 +2690,7 @@ mod tests {
         assert_eq!(opponent.life, 19);
 
         let player = game.get_player(player_id).unwrap();
-        assert_eq!(player.mana.red, 0);
+        assert_eq!(player.mana.total().red, 0);
     }
 
     #[test]
@@ -744,6 +2703,7 @@ mod tests {
             cost: Cost::Tap(Target::Source),
             effect: Effect::Mana(Mana::from("G")),
             target: Target::Owner,
+            restrictions: vec![],
         });
         let card_id = game.add_card(card);
         put_on_battlefield(&mut game, card_id);
@@ -766,6 +2726,7 @@ mod tests {
             cost: Cost::Tap(Target::Source),
             effect: Effect::Mana(Mana::from("G")),
             target: Target::Owner,
+            restrictions: vec![],
         });
         let card_id = game.add_card(card);
         put_on_battlefield(&mut game, card_id);
@@ -777,18 +2738,16 @@ mod tests {
         assert!(play_ability(&mut game, card_id, 0, action));
 
         let player = game.get_player(player_id).unwrap();
-        assert_eq!(player.mana.green, 1);
+        assert_eq!(player.mana.total().green, 1);
     }
 
     #[test]
     fn test_activate_ability_any_of_target_creature() {
         let (mut game, player_id, _) = Game::new();
-        add_mana(&mut game, player_id, Mana::from("R"));
-
         let mut card = Card::new_sorcery(player_id);
         card.cost = Cost::Mana("R");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::Damage(2),
+            effect: Effect::Damage(Amount::Fixed(2)),
             target: Target::AnyOf(&[Target::Player, Target::Creature]),
         });
         let sorcery_id = game.add_card(card);
@@ -797,6 +2756,7 @@ mod tests {
         let creature_id = game.add_card(Card::new_creature(player_id, 2, 2));
         put_on_battlefield(&mut game, creature_id);
         precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("R"));
 
         let mut action = create_card_action(&mut game, sorcery_id, player_id).unwrap();
         action.choices.cost = Choice::Mana(Mana::from("R"));
@@ -844,7 +2804,7 @@ mod tests {
         let mut card = Card::new_instant(opponent_id);
         card.cost = Cost::Mana("R");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::Damage(3),
+            effect: Effect::Damage(Amount::Fixed(3)),
             target: Target::AnyOf(&[Target::Player, Target::Creature]),
         });
         let card_id = game.add_card(card);
@@ -871,7 +2831,7 @@ mod tests {
         let mut card = Card::new_instant(player_id);
         card.cost = Cost::Mana("R");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::Damage(3),
+            effect: Effect::Damage(Amount::Fixed(3)),
             target: Target::AnyOf(&[Target::Player, Target::Creature]),
         });
         let player_card = game.add_card(card.clone());
@@ -891,7 +2851,7 @@ mod tests {
         let mut card = Card::new_instant(player_id);
         card.cost = Cost::Mana("U");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::Draw(3),
+            effect: Effect::Draw(Amount::Fixed(3)),
             target: Target::Player,
         });
         let card_id = game.add_card(card.clone());
@@ -937,7 +2897,10 @@ mod tests {
         let mut card = Card::new_sorcery(player_id);
         card.cost = Cost::Mana("U");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::And(VecDeque::from([Effect::Draw(1), Effect::Discard(1)])),
+            effect: Effect::And(VecDeque::from([
+                Effect::Draw(Amount::Fixed(1)),
+                Effect::Discard(Amount::Fixed(1)),
+            ])),
             target: Target::Owner,
         });
         let card_id = game.add_card(card.clone());
@@ -965,7 +2928,7 @@ mod tests {
         assert_eq!(
             next_choice,
             ResolveChoice {
-                effect: Effect::Discard(1),
+                effect: Effect::Discard(Amount::Fixed(1)),
                 choice: Choice::None,
                 player_id
             }
@@ -995,7 +2958,7 @@ mod tests {
         let mut card = Card::new_sorcery(player_id);
         card.cost = Cost::Mana("B");
         card.play_ability = Some(PlayAbility {
-            effect: Effect::Discard(1),
+            effect: Effect::Discard(Amount::Fixed(1)),
             target: Target::Player,
         });
         let card_id = game.add_card(card.clone());
@@ -1019,7 +2982,7 @@ mod tests {
             next_choice,
             ResolveChoice {
                 player_id: opponent_id,
-                effect: Effect::Discard(1),
+                effect: Effect::Discard(Amount::Fixed(1)),
                 choice: Choice::None
             }
         );
@@ -1038,12 +3001,14 @@ mod tests {
         card.subtypes.insert(CardSubtype::Spirit);
         card.activated_abilities.push(ActivatedAbility {
             cost: Cost::And(&[Cost::Mana("R"), Cost::Sacrifice(Target::Creature)]),
-            effect: Effect::Damage(1),
+            effect: Effect::Damage(Amount::Fixed(1)),
             target: Target::Player,
+            restrictions: vec![],
         });
         let card_id = game.add_card(card);
 
         put_on_battlefield(&mut game, card_id);
+        upkeep_step(&mut game);
         add_mana(&mut game, player_id, Mana::from("R"));
 
         let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
@@ -1061,241 +3026,3096 @@ mod tests {
         assert_eq!(card.zone, Zone::Graveyard);
     }
 
+    fn create_pinger(game: &mut Game, player_id: ObjectId, cost: Cost) -> ObjectId {
+        let mut card = Card::new_artifact(player_id);
+        card.activated_abilities.push(ActivatedAbility {
+            cost,
+            effect: Effect::Damage(Amount::Fixed(1)),
+            target: Target::Player,
+            restrictions: vec![],
+        });
+        let card_id = game.add_card(card);
+        put_on_battlefield(game, card_id);
+        card_id
+    }
+
     #[test]
-    fn test_flying() {
+    fn test_sacrifice_two_creatures_cost() {
         let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(
+            &mut game,
+            player_id,
+            Cost::And(&[
+                Cost::Sacrifice(Target::Creature),
+                Cost::Sacrifice(Target::Creature),
+            ]),
+        );
+        let first_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, first_id);
+        let second_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, second_id);
 
-        let mut card = Card::new_creature(player_id, 2, 2);
-        card.subtypes.insert(CardSubtype::Dragon);
-        card.static_abilities.insert(StaticAbility::Flying);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
-
-        let mut card = Card::new_creature(opponent_id, 1, 1);
-        card.subtypes.insert(CardSubtype::Human);
-        let human_id = game.add_card(card);
-        put_on_battlefield(&mut game, human_id);
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::And(vec![Choice::Card(first_id), Choice::Card(first_id)]);
+        assert!(!action.valid(&mut game));
 
-        let mut card = Card::new_creature(opponent_id, 2, 2);
-        card.subtypes.insert(CardSubtype::Spider);
-        card.static_abilities.insert(StaticAbility::Reach);
-        let spider_id = game.add_card(card);
-        put_on_battlefield(&mut game, spider_id);
+        action.choices.cost = Choice::And(vec![Choice::Card(first_id), Choice::Card(second_id)]);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_card(first_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_card(second_id).unwrap().zone, Zone::Graveyard);
+    }
 
-        let mut card = Card::new_creature(opponent_id, 1, 1);
+    #[test]
+    fn test_tap_untapped_creature_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(&mut game, player_id, Cost::Tap(Target::Creature));
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+        let opponent_creature = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, opponent_creature);
+
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Card(opponent_creature);
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::Card(creature_id);
+        assert!(play_ability(&mut game, card_id, 0, action.clone()));
+        assert!(game.get_card(creature_id).unwrap().state.tapped.current);
+
+        // The creature is already tapped
+        assert!(!action.valid(&mut game));
+    }
+
+    #[test]
+    fn test_discard_and_life_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(
+            &mut game,
+            player_id,
+            Cost::And(&[Cost::Discard(Target::Card), Cost::Life(2)]),
+        );
+        let land_id = game.add_card(Card::new_land(player_id));
+        put_in_hand(&mut game, land_id);
+
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::Card(land_id);
+        assert!(play_ability(&mut game, card_id, 0, action.clone()));
+        assert_eq!(game.get_card(land_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_player(player_id).unwrap().life, 18);
+
+        game.get_player(player_id).unwrap().life = 1;
+        put_in_hand(&mut game, land_id);
+        assert!(!action.valid(&mut game));
+
+        // Paying the remaining life loses the game
+        game.get_player(player_id).unwrap().life = 2;
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_player(player_id).unwrap().life, 0);
+        assert_eq!(game.status, GameStatus::Lose(player_id));
+    }
+
+    #[test]
+    fn test_exile_from_graveyard_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(
+            &mut game,
+            player_id,
+            Cost::ExileFromGraveyard(Target::Creature),
+        );
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Card(creature_id);
+        assert!(!action.valid(&mut game));
+
+        put_on_graveyard(&mut game, creature_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Exile);
+        assert!(game
+            .get_player(player_id)
+            .unwrap()
+            .exile
+            .contains(&creature_id));
+    }
+
+    #[test]
+    fn test_remove_counters_and_return_to_hand_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(
+            &mut game,
+            player_id,
+            Cost::And(&[
+                Cost::RemoveCounters(Counter::Charge, 2),
+                Cost::ReturnToHand(Target::Source),
+            ]),
+        );
+        game.get_card(card_id)
+            .unwrap()
+            .state
+            .add_counters(Counter::Charge, 1);
+
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Card(card_id);
+        assert!(!action.valid(&mut game));
+
+        game.get_card(card_id)
+            .unwrap()
+            .state
+            .add_counters(Counter::Charge, 1);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Hand);
+    }
+
+    #[test]
+    fn test_kicker() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let effect = Effect::And(VecDeque::from([
+            Effect::Damage(Amount::Fixed(1)),
+            Effect::Kicked(Box::new(Effect::Damage(Amount::Fixed(2)))),
+        ]));
+        let card_id = create_burn(&mut game, player_id, effect);
+        let kicker = AdditionalCost::Multikicker(Cost::Mana("R"));
+        game.get_card(card_id)
+            .unwrap()
+            .additional_costs
+            .push(kicker.clone());
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("RRRR"));
+
+        let costs = CastCosts {
+            additional: vec![kicker.clone(), kicker],
+            ..Default::default()
+        };
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Mana(Mana::from("RRR"));
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::Mana(Mana::from("RRRR"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 15);
+    }
+
+    #[test]
+    fn test_kicker_not_paid() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_burn(
+            &mut game,
+            player_id,
+            Effect::Kicked(Box::new(Effect::Damage(Amount::Fixed(2)))),
+        );
+        let kicker = AdditionalCost::Kicker(Cost::Mana("R"));
+        game.get_card(card_id)
+            .unwrap()
+            .additional_costs
+            .push(kicker.clone());
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("RRR"));
+
+        let costs = CastCosts {
+            additional: vec![kicker.clone(), kicker],
+            ..Default::default()
+        };
+        assert!(create_card_action_with_costs(&mut game, card_id, player_id, costs).is_none());
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Mana(Mana::from("RR"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 20);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_buyback() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_burn(&mut game, player_id, Effect::Damage(Amount::Fixed(1)));
+        let buyback = AdditionalCost::Buyback(Cost::Mana("3"));
+        game.get_card(card_id)
+            .unwrap()
+            .additional_costs
+            .push(buyback.clone());
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("4R"));
+
+        let costs = CastCosts {
+            additional: vec![buyback],
+            ..Default::default()
+        };
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Mana(Mana::from("4R"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Hand);
+    }
+
+    #[test]
+    fn test_flashback() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_burn(&mut game, player_id, Effect::Damage(Amount::Fixed(1)));
+        let flashback = AlternativeCost::Flashback(Cost::Mana("3R"));
+        game.get_card(card_id)
+            .unwrap()
+            .alternative_costs
+            .push(flashback.clone());
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("3R"));
+
+        let costs = CastCosts {
+            alternative: Some(flashback),
+            ..Default::default()
+        };
+        assert!(
+            create_card_action_with_costs(&mut game, card_id, player_id, costs.clone()).is_none()
+        );
+
+        put_on_graveyard(&mut game, card_id);
+        assert!(create_card_action(&mut game, card_id, player_id).is_none());
+
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.cost = Choice::Mana(Mana::from("3R"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Exile);
+    }
+
+    #[test]
+    fn test_evoke() {
+        let (mut game, player_id, _) = Game::new();
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.cost = Cost::Mana("4W");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::None,
+            target: Target::None,
+        });
+        card.alternative_costs
+            .push(AlternativeCost::Evoke(Cost::Mana("W")));
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("W"));
+
+        let costs = CastCosts {
+            alternative: Some(AlternativeCost::Evoke(Cost::Mana("W"))),
+            ..Default::default()
+        };
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("W"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_cast_without_paying_mana_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_burn(&mut game, player_id, Effect::Damage(Amount::Fixed(1)));
+        precombat_step(&mut game);
+
+        let costs = CastCosts {
+            alternative: Some(AlternativeCost::Free),
+            ..Default::default()
+        };
+        assert!(
+            create_card_action_with_costs(&mut game, card_id, player_id, costs.clone()).is_none()
+        );
+
+        game.get_card(card_id)
+            .unwrap()
+            .alternative_costs
+            .push(AlternativeCost::Free);
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(action.valid(&mut game));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+    }
+
+    #[test]
+    fn test_cost_modifiers() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_burn(&mut game, player_id, Effect::Damage(Amount::Fixed(1)));
+
+        let mut card = Card::new_artifact(player_id);
+        card.static_abilities
+            .insert(StaticAbility::CostReduction(Some(CardType::Sorcery), 1));
+        let reducer_id = game.add_card(card);
+        put_on_battlefield(&mut game, reducer_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("RR"));
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert_eq!(action.get_mana_cost(&mut game), Ok("R".parse().unwrap()));
+
+        let mut card = Card::new_artifact(opponent_id);
+        card.static_abilities
+            .insert(StaticAbility::OpponentCostIncrease(None, 2));
+        let taxer_id = game.add_card(card);
+        put_on_battlefield(&mut game, taxer_id);
+        assert_eq!(action.get_mana_cost(&mut game), Ok("2R".parse().unwrap()));
+
+        action.choices.cost = Choice::Mana(Mana::from("RR"));
+        assert!(!action.valid(&mut game));
+    }
+
+    #[test]
+    fn test_convoke() {
+        let (mut game, player_id, _) = Game::new();
+        let mut card = Card::new_sorcery(player_id);
+        card.cost = Cost::Mana("1W");
+        card.static_abilities.insert(StaticAbility::Convoke);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Draw(Amount::Fixed(1)),
+            target: Target::Owner,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.cost = Cost::Mana("W");
+        let white_id = game.add_card(card);
+        put_on_battlefield(&mut game, white_id);
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.cost = Cost::Mana("R");
+        let red_id = game.add_card(card);
+        put_on_battlefield(&mut game, red_id);
+        precombat_step(&mut game);
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::And(vec![
+            Choice::Substitute(red_id, ManaSymbol::Colored(Color::White)),
+            Choice::Substitute(white_id, ManaSymbol::Generic(1)),
+        ]);
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::And(vec![
+            Choice::Substitute(red_id, ManaSymbol::Generic(1)),
+            Choice::Substitute(red_id, ManaSymbol::Colored(Color::White)),
+        ]);
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::And(vec![
+            Choice::Substitute(white_id, ManaSymbol::Colored(Color::White)),
+            Choice::Substitute(red_id, ManaSymbol::Generic(1)),
+        ]);
+        assert!(action.valid(&mut game));
+        play_card(&mut game, card_id, action);
+
+        assert_eq!(game.stack.len(), 1);
+        assert!(game.get_card(white_id).unwrap().state.tapped.current);
+        assert!(game.get_card(red_id).unwrap().state.tapped.current);
+    }
+
+    #[test]
+    fn test_delve_and_improvise() {
+        let (mut game, player_id, _) = Game::new();
+        let mut card = Card::new_sorcery(player_id);
+        card.cost = Cost::Mana("2U");
+        card.static_abilities.insert(StaticAbility::Delve);
+        card.static_abilities.insert(StaticAbility::Improvise);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Draw(Amount::Fixed(1)),
+            target: Target::Owner,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        let dead_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_graveyard(&mut game, dead_id);
+        let artifact_id = game.add_card(Card::new_artifact(player_id));
+        put_on_battlefield(&mut game, artifact_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("U"));
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::And(vec![
+            Choice::Substitute(dead_id, ManaSymbol::Colored(Color::Blue)),
+            Choice::Substitute(artifact_id, ManaSymbol::Generic(1)),
+            Choice::Mana(Mana::from("U")),
+        ]);
+        assert!(!action.valid(&mut game));
+
+        action.choices.cost = Choice::And(vec![
+            Choice::Substitute(dead_id, ManaSymbol::Generic(1)),
+            Choice::Substitute(artifact_id, ManaSymbol::Generic(1)),
+            Choice::Mana(Mana::from("U")),
+        ]);
+        assert!(action.valid(&mut game));
+        play_card(&mut game, card_id, action);
+
+        assert_eq!(game.get_card(dead_id).unwrap().zone, Zone::Exile);
+        assert!(game.get_card(artifact_id).unwrap().state.tapped.current);
+        assert!(game.get_player(player_id).unwrap().mana.is_empty());
+    }
+
+    fn create_restricted_pinger(
+        game: &mut Game,
+        player_id: ObjectId,
+        restriction: ActivationRestriction,
+    ) -> ObjectId {
+        let card_id = create_pinger(game, player_id, Cost::None);
+        game.get_card(card_id).unwrap().activated_abilities[0]
+            .restrictions
+            .push(restriction);
+        card_id
+    }
+
+    #[test]
+    fn test_activate_ability_requires_priority_and_control() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_pinger(&mut game, player_id, Cost::None);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, opponent_id, card_id, 0).is_none());
+
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        pass_priority(&mut game);
+        assert!(!play_ability(&mut game, card_id, 0, action.clone()));
+
+        action.player_id = opponent_id;
+        action.choices.target = Choice::Player(player_id);
+        assert!(!play_ability(&mut game, card_id, 0, action));
+    }
+
+    #[test]
+    fn test_activate_only_as_sorcery() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id =
+            create_restricted_pinger(&mut game, player_id, ActivationRestriction::SorcerySpeed);
+
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+
+        // The stack is not empty
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+    }
+
+    #[test]
+    fn test_activate_only_once_each_turn() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id =
+            create_restricted_pinger(&mut game, player_id, ActivationRestriction::OncePerTurn);
+        upkeep_step(&mut game);
+
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 0, action.clone()));
+        resolve_auto(&mut game);
+        assert!(!play_ability(&mut game, card_id, 0, action));
+    }
+
+    #[test]
+    fn test_activate_only_during_upkeep() {
+        let (mut game, player_id, _) = Game::new();
+        let card_id = create_restricted_pinger(
+            &mut game,
+            player_id,
+            ActivationRestriction::Step(Step::Upkeep),
+        );
+
+        precombat_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_some());
+    }
+
+    #[test]
+    fn test_activate_only_if_control() {
+        let (mut game, player_id, _) = Game::new();
+        let card_id = create_restricted_pinger(
+            &mut game,
+            player_id,
+            ActivationRestriction::Control(Filter::Quality(Quality::Type(CardType::Creature))),
+        );
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_some());
+    }
+
+    #[test]
+    fn test_artifact_creature() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.types.insert(CardType::Artifact);
+        card.cost = Cost::Mana("2");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::None,
+            target: Target::None,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("RR"));
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("RR"));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        let card = game.get_card(card_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert!(card.is_creature() && card.is_type(&CardType::Artifact));
+
+        // Artifact creatures are affected by the abilities regarding both types
+        let mut card = Card::new_creature(opponent_id, 1, 1);
+        card.static_abilities
+            .insert(StaticAbility::Protection(Quality::Type(CardType::Artifact)));
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+        assert_eq!(deal_damage(&mut game, card_id, blocker_id, 2), 0);
+        assert_eq!(deal_damage(&mut game, blocker_id, card_id, 2), 2);
+    }
+
+    fn create_aura(game: &mut Game, player_id: ObjectId) -> ObjectId {
+        let mut card = Card::new_enchantment(player_id);
+        card.subtypes.insert(CardSubtype::Aura);
+        card.static_abilities
+            .insert(StaticAbility::AttachedModifier(2, 2));
+        card.static_abilities
+            .insert(StaticAbility::AttachedAbility(&StaticAbility::Flying));
+        create_spell(game, card, "W", Effect::None, Target::Creature)
+    }
+
+    fn cast_aura(game: &mut Game, card_id: ObjectId, player_id: ObjectId, target_id: ObjectId) {
+        precombat_step(game);
+        add_mana(game, player_id, Mana::from("W"));
+        let action = create_sorcery_action(game, card_id, player_id, Choice::Card(target_id));
+        play_card(game, card_id, action);
+    }
+
+    #[test]
+    fn test_aura() {
+        let (mut game, player_id, _) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+
+        let aura_id = create_aura(&mut game, player_id);
+        cast_aura(&mut game, aura_id, player_id, creature_id);
+        resolve_auto(&mut game);
+
+        let aura = game.get_card(aura_id).unwrap();
+        assert_eq!(aura.zone, Zone::Battlefield);
+        assert_eq!(aura.state.attached_to, Some(creature_id));
+
+        let creature = game.get_card(creature_id).unwrap();
+        assert_eq!(creature.state.power.current, 3);
+        assert_eq!(creature.state.toughness.current, 3);
+        assert!(creature.static_abilities.contains(&StaticAbility::Flying));
+
+        // The bonuses last while the aura is attached
+        creature.state.restore();
+        assert_eq!(creature.state.power.current, 3);
+
+        // The aura is put into the graveyard when the enchanted creature leaves
+        put_in_hand(&mut game, creature_id);
+        check_state_based_actions(&mut game);
+        assert_eq!(game.get_card(aura_id).unwrap().zone, Zone::Graveyard);
+        let creature = game.get_card(creature_id).unwrap();
+        assert!(!creature.static_abilities.contains(&StaticAbility::Flying));
+    }
+
+    #[test]
+    fn test_aura_with_illegal_target() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+
+        let aura_id = create_aura(&mut game, player_id);
+        cast_aura(&mut game, aura_id, player_id, creature_id);
+        game.get_card(creature_id)
+            .unwrap()
+            .static_abilities
+            .insert(StaticAbility::Protection(Quality::Color(Color::White)));
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_card(aura_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_card(creature_id).unwrap().state.power.current, 1);
+    }
+
+    #[test]
+    fn test_equipment() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+        let opponent_creature_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, opponent_creature_id);
+
+        let mut card = Card::new_artifact(player_id);
+        card.subtypes.insert(CardSubtype::Equipment);
+        card.static_abilities
+            .insert(StaticAbility::AttachedModifier(2, 0));
+        card.activated_abilities
+            .push(ActivatedAbility::equip(Cost::Mana("1")));
+        let equipment_id = game.add_card(card);
+        put_on_battlefield(&mut game, equipment_id);
+
+        // Equip can be activated only as a sorcery
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, equipment_id, 0).is_none());
+
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("CC"));
+        let mut action = create_ability_action(&mut game, player_id, equipment_id, 0).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("C"));
+        action.choices.target = Choice::Card(opponent_creature_id);
+        assert!(play_ability(&mut game, equipment_id, 0, action.clone()));
+        start_resolve(&mut game);
+        assert_eq!(
+            resolve_choice(&mut game, ResolveChoice::default()),
+            Err(ResolveError::InvalidTarget)
+        );
+        end_resolve(&mut game);
+
+        game.turn.activated_abilities.clear();
+        action.choices.target = Choice::Card(creature_id);
+        assert!(play_ability(&mut game, equipment_id, 0, action));
+        resolve_auto(&mut game);
+        assert_eq!(
+            game.get_card(equipment_id).unwrap().state.attached_to,
+            Some(creature_id)
+        );
+        assert_eq!(game.get_card(creature_id).unwrap().state.power.current, 3);
+
+        // Equipment stays on the battlefield when the creature leaves
+        put_on_graveyard(&mut game, creature_id);
+        check_state_based_actions(&mut game);
+        let equipment = game.get_card(equipment_id).unwrap();
+        assert_eq!(equipment.zone, Zone::Battlefield);
+        assert_eq!(equipment.state.attached_to, None);
+    }
+
+    #[test]
+    fn test_attachments_of_creature_dying_in_combat() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+        // The aura grants flying to the attacker
+        let mut card = Card::new_creature(opponent_id, 3, 5);
+        card.static_abilities.insert(StaticAbility::Reach);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        let mut card = Card::new_artifact(player_id);
+        card.subtypes.insert(CardSubtype::Equipment);
+        card.static_abilities
+            .insert(StaticAbility::AttachedModifier(1, 0));
+        let equipment_id = game.add_card(card);
+        put_on_battlefield(&mut game, equipment_id);
+        assert!(attach(&mut game, equipment_id, attacker_id));
+
+        let aura_id = create_aura(&mut game, player_id);
+        put_on_battlefield(&mut game, aura_id);
+        assert!(attach(&mut game, aura_id, attacker_id));
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        assert_eq!(game.get_card(attacker_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_card(blocker_id).unwrap().state.damage, 4);
+
+        // The attachments are checked again after the creature dies
+        let equipment = game.get_card(equipment_id).unwrap();
+        assert_eq!(equipment.zone, Zone::Battlefield);
+        assert_eq!(equipment.state.attached_to, None);
+        assert_eq!(game.get_card(aura_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    const SPIRIT: Token = Token::Creature {
+        subtype: CardSubtype::Spirit,
+        color: Color::White,
+        power: 1,
+        toughness: 1,
+        abilities: &[StaticAbility::Flying],
+    };
+
+    fn get_tokens(game: &mut Game, player_id: ObjectId) -> Vec<ObjectId> {
+        let battlefield = game.get_player(player_id).unwrap().battlefield.clone();
+        battlefield
+            .into_iter()
+            .filter(|card_id| game.get_card(*card_id).unwrap().token)
+            .collect()
+    }
+
+    #[test]
+    fn test_create_creature_tokens() {
+        let (mut game, player_id, _) = Game::new();
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::CreateToken(SPIRIT, 2),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+
+        let tokens = get_tokens(&mut game, player_id);
+        assert_eq!(tokens.len(), 2);
+        let token = game.get_card(tokens[0]).unwrap();
+        assert!(token.is_creature());
+        assert!(token.subtypes.contains(&CardSubtype::Spirit));
+        assert!(token.has_color(&Color::White));
+        assert!(token.static_abilities.contains(&StaticAbility::Flying));
+        assert_eq!(token.state.power.current, 1);
+        assert_eq!(token.state.toughness.current, 1);
+    }
+
+    #[test]
+    fn test_create_treasure_for_target_player() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::CreateToken(Token::Treasure, 1),
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+        assert!(get_tokens(&mut game, player_id).is_empty());
+
+        let treasure_id = get_tokens(&mut game, opponent_id)[0];
+        assert!(game
+            .get_card(treasure_id)
+            .unwrap()
+            .is_type(&CardType::Artifact));
+
+        // Treasure is sacrificed for mana and ceases to exist
+        game.turn.priority = Some(Priority::new(opponent_id));
+        let mut action = create_ability_action(&mut game, opponent_id, treasure_id, 0).unwrap();
+        action.choices.cost =
+            Choice::And(vec![Choice::Card(treasure_id), Choice::Card(treasure_id)]);
+        action.choices.effect = Choice::Mana(Mana::from("G"));
+        assert!(play_ability(&mut game, treasure_id, 0, action));
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.mana.total(), Mana::from("G"));
+        assert!(opponent.graveyard.is_empty());
+        assert!(game.get_card(treasure_id).is_none());
+    }
+
+    #[test]
+    fn test_create_token_copy() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(opponent_id, 3, 3);
+        card.name = "Hill Giant".to_owned();
+        let creature_id = game.add_card(card);
+        put_on_battlefield(&mut game, creature_id);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::CreateToken(Token::Copy, 1),
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+
+        let tokens = get_tokens(&mut game, player_id);
+        assert_eq!(tokens.len(), 1);
+        let token = game.get_card(tokens[0]).unwrap();
+        assert_eq!(token.name, "Hill Giant");
+        assert_eq!(token.owner_id, player_id);
+        assert_eq!(token.state.power.current, 3);
+    }
+
+    #[test]
+    fn test_token_enters_with_triggers() {
+        let (mut game, player_id, _) = Game::new();
+        let mut card = Card::new_enchantment(player_id);
+        card.triggered_abilities.push(TriggeredAbility {
+            condition: Condition::EnterBattlefield(Target::Card),
+            effect: Effect::Draw(Amount::Fixed(1)),
+            target: Target::Owner,
+        });
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+
+        // The enchantment triggers on entering the battlefield itself
+        assert_eq!(game.stack.len(), 1);
+        game.stack.clear();
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::CreateToken(SPIRIT, 2),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(game.stack.len(), 2);
+    }
+
+    #[test]
+    fn test_token_ceases_to_exist() {
+        let (mut game, player_id, _) = Game::new();
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::CreateToken(SPIRIT, 2),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        let tokens = get_tokens(&mut game, player_id);
+
+        put_on_graveyard(&mut game, tokens[0]);
+        put_on_deck_top(&mut game, tokens[1], player_id);
+        let player = game.get_player(player_id).unwrap();
+        assert!(player.graveyard.contains(&tokens[0]));
+        assert!(!player.library.contains(&tokens[1]));
+
+        check_state_based_actions(&mut game);
+        let player = game.get_player(player_id).unwrap();
+        assert!(!player.graveyard.contains(&tokens[0]));
+        assert!(game.get_card(tokens[0]).is_none());
+        assert!(game.get_card(tokens[1]).is_none());
+    }
+
+    fn create_watcher(game: &mut Game, player_id: ObjectId, condition: Condition) -> ObjectId {
+        let mut card = Card::new_enchantment(player_id);
+        card.triggered_abilities.push(TriggeredAbility {
+            condition,
+            effect: Effect::None,
+            target: Target::None,
+        });
+        let card_id = game.add_card(card);
+        put_on_battlefield(game, card_id);
+        card_id
+    }
+
+    #[test]
+    fn test_destroy_target_creature() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.triggered_abilities.push(TriggeredAbility {
+            condition: Condition::Dies(Target::Source),
+            effect: Effect::None,
+            target: Target::None,
+        });
+        let creature_id = game.add_card(card);
+        put_on_battlefield(&mut game, creature_id);
+
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Indestructible);
+        let indestructible_id = game.add_card(card);
+        put_on_battlefield(&mut game, indestructible_id);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Destroy,
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        // The creature triggers on dying
+        assert_eq!(game.stack.len(), 1);
+
+        game.stack.clear();
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Destroy,
+            Target::Creature,
+            Choice::Card(indestructible_id),
+        );
+        assert_eq!(
+            game.get_card(indestructible_id).unwrap().zone,
+            Zone::Battlefield
+        );
+    }
+
+    #[test]
+    fn test_move_target_permanent() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        create_watcher(
+            &mut game,
+            player_id,
+            Condition::LeaveBattlefield(Target::Card),
+        );
+
+        let mut permanents = vec![];
+        for _ in 0..3 {
+            let card_id = game.add_card(Card::new_artifact(opponent_id));
+            put_on_battlefield(&mut game, card_id);
+            permanents.push(card_id);
+        }
+
+        for (effect, card_id) in [Effect::Exile, Effect::ReturnToHand, Effect::PutOnLibrary]
+            .into_iter()
+            .zip(permanents.iter())
+        {
+            cast_sorcery(
+                &mut game,
+                player_id,
+                effect,
+                Target::Card,
+                Choice::Card(*card_id),
+            );
+            // The permanent leaving the battlefield is seen by the watcher
+            assert_eq!(game.stack.len(), 1);
+            game.stack.clear();
+        }
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert!(opponent.exile.contains(&permanents[0]));
+        assert!(opponent.hand.contains(&permanents[1]));
+        assert_eq!(opponent.library.last(), Some(&permanents[2]));
+        assert!(opponent.battlefield.is_empty());
+    }
+
+    #[test]
+    fn test_target_permanent_with_hexproof() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_artifact(opponent_id);
+        card.static_abilities.insert(StaticAbility::Hexproof);
+        let hexproof_id = game.add_card(card);
+        put_on_battlefield(&mut game, hexproof_id);
+        let graveyard_id = game.add_card(Card::new_artifact(opponent_id));
+        put_on_graveyard(&mut game, graveyard_id);
+
+        let card_id = create_sorcery(&mut game, player_id, Effect::Exile, Target::Card);
+        for target_id in [hexproof_id, graveyard_id] {
+            let action =
+                create_sorcery_action(&mut game, card_id, player_id, Choice::Card(target_id));
+            assert!(!action.valid(&mut game));
+        }
+    }
+
+    #[test]
+    fn test_target_permanent_leaves_battlefield() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let artifact_id = game.add_card(Card::new_artifact(opponent_id));
+        put_on_battlefield(&mut game, artifact_id);
+
+        let card_id = create_sorcery(&mut game, player_id, Effect::Exile, Target::Card);
+        let action =
+            create_sorcery_action(&mut game, card_id, player_id, Choice::Card(artifact_id));
+        play_card(&mut game, card_id, action);
+
+        // The spell is countered since its target is no longer on the battlefield
+        put_on_graveyard(&mut game, artifact_id);
+        let action = game.stack[0].action.clone();
+        assert!(!action.has_legal_target(&mut game));
+        resolve_auto(&mut game);
+        assert_eq!(game.get_card(artifact_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_tap_and_untap_target_permanent() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Tap,
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+        assert!(game.get_card(creature_id).unwrap().state.tapped.current);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Untap,
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+        assert!(!game.get_card(creature_id).unwrap().state.tapped.current);
+    }
+
+    #[test]
+    fn test_destroy_each_creature() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        create_watcher(&mut game, player_id, Condition::Dies(Target::Card));
+
+        let mut creatures = vec![];
+        for owner_id in [player_id, opponent_id] {
+            let creature_id = game.add_card(Card::new_creature(owner_id, 2, 2));
+            put_on_battlefield(&mut game, creature_id);
+            creatures.push(creature_id);
+        }
+        let artifact_id = game.add_card(Card::new_artifact(opponent_id));
+        put_on_battlefield(&mut game, artifact_id);
+
+        let effect = Effect::All(
+            Filter::Quality(Quality::Type(CardType::Creature)),
+            Box::new(Effect::Destroy),
+        );
+        cast_sorcery(&mut game, player_id, effect, Target::None, Choice::None);
+
+        for creature_id in creatures {
+            assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        }
+        assert_eq!(game.get_card(artifact_id).unwrap().zone, Zone::Battlefield);
+        assert_eq!(game.stack.len(), 2);
+    }
+
+    #[test]
+    fn test_mill() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let library = create_library(&mut game, opponent_id, 3);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Mill(Amount::Fixed(2)),
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert!(opponent.graveyard.contains(&library[0]));
+        assert!(opponent.graveyard.contains(&library[1]));
+        assert_eq!(opponent.library.len(), 1);
+    }
+
+    #[test]
+    fn test_scry() {
+        let (mut game, player_id, _) = Game::new();
+        let library = create_library(&mut game, player_id, 3);
+        let effect = Effect::Scry(2);
+
+        // Every card looked at must be arranged
+        play_sorcery(
+            &mut game,
+            player_id,
+            effect.clone(),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        let choice = Choice::Arrange {
+            top: vec![library[1]],
+            bottom: vec![],
+            graveyard: vec![],
+        };
+        assert_eq!(
+            resolve_with_choice(&mut game, choice),
+            Err(ResolveError::InvalidChoice)
+        );
+
+        play_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        let choice = Choice::Arrange {
+            top: vec![library[1]],
+            bottom: vec![library[0]],
+            graveyard: vec![],
+        };
+        assert_eq!(resolve_with_choice(&mut game, choice), Ok(()));
+
+        let player = game.get_player(player_id).unwrap();
+        let order: Vec<ObjectId> = player.library.iter().rev().cloned().collect();
+        assert_eq!(order, vec![library[1], library[2], library[0]]);
+    }
+
+    #[test]
+    fn test_surveil() {
+        let (mut game, player_id, _) = Game::new();
+        let library = create_library(&mut game, player_id, 3);
+
+        play_sorcery(
+            &mut game,
+            player_id,
+            Effect::Surveil(2),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        let choice = Choice::Arrange {
+            top: vec![library[1]],
+            bottom: vec![],
+            graveyard: vec![library[0]],
+        };
+        assert_eq!(resolve_with_choice(&mut game, choice), Ok(()));
+
+        let player = game.get_player(player_id).unwrap();
+        assert!(player.graveyard.contains(&library[0]));
+        assert_eq!(player.library.last(), Some(&library[1]));
+    }
+
+    #[test]
+    fn test_search_library() {
+        let (mut game, player_id, _) = Game::new();
+        let library = create_library(&mut game, player_id, 3);
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_deck_bottom(&mut game, creature_id, player_id);
+
+        let effect = Effect::Search(
+            Filter::Quality(Quality::Type(CardType::Creature)),
+            Zone::Hand,
+        );
+        play_sorcery(
+            &mut game,
+            player_id,
+            effect.clone(),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(
+            resolve_with_choice(&mut game, Choice::Card(library[1])),
+            Err(ResolveError::InvalidChoice)
+        );
+
+        play_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(
+            resolve_with_choice(&mut game, Choice::Card(creature_id)),
+            Ok(())
+        );
+
+        let player = game.get_player(player_id).unwrap();
+        assert!(player.hand.contains(&creature_id));
+        assert_eq!(player.library.len(), 3);
+    }
+
+    #[test]
+    fn test_reveal_top() {
+        let (mut game, player_id, _) = Game::new();
+        let library = create_library(&mut game, player_id, 4);
+
+        play_sorcery(
+            &mut game,
+            player_id,
+            Effect::RevealTop(3),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(
+            resolve_with_choice(&mut game, Choice::Card(library[1])),
+            Ok(())
+        );
+
+        let player = game.get_player(player_id).unwrap();
+        assert!(player.hand.contains(&library[1]));
+        let order: Vec<ObjectId> = player.library.iter().rev().cloned().collect();
+        assert_eq!(order, vec![library[3], library[0], library[2]]);
+    }
+
+    #[test]
+    fn test_burn_and_gain_life() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let effect = Effect::And(VecDeque::from([
+            Effect::Damage(Amount::Fixed(3)),
+            Effect::GainLife(Amount::Fixed(3)),
+        ]));
+        cast_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 17);
+        assert_eq!(game.get_player(player_id).unwrap().life, 23);
+    }
+
+    #[test]
+    fn test_lose_life() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let effect = Effect::And(VecDeque::from([
+            Effect::LoseLife(Amount::Fixed(2)),
+            Effect::GainLife(Amount::Fixed(2)),
+        ]));
+        cast_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+        assert_eq!(game.get_player(player_id).unwrap().life, 22);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::LoseLife(Amount::Fixed(18)),
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+        assert_eq!(game.status, GameStatus::Lose(opponent_id));
+    }
+
+    #[test]
+    fn test_set_life() {
+        let (mut game, player_id, _) = Game::new();
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::SetLife(10),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(game.get_player(player_id).unwrap().life, 10);
+    }
+
+    #[test]
+    fn test_pump_until_end_of_turn() {
+        let (mut game, player_id, _) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(player_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+        game.get_card(creature_id)
+            .unwrap()
+            .state
+            .add_counters(Counter::Plus, 1);
+
+        let mut card = Card::new_artifact(player_id);
+        card.subtypes.insert(CardSubtype::Equipment);
+        card.static_abilities
+            .insert(StaticAbility::AttachedModifier(1, 1));
+        let equipment_id = game.add_card(card);
+        put_on_battlefield(&mut game, equipment_id);
+        assert!(attach(&mut game, equipment_id, creature_id));
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Pump(2, 2),
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+        let creature = game.get_card(creature_id).unwrap();
+        assert_eq!(creature.state.power.current, 6);
+        assert_eq!(creature.state.toughness.current, 6);
+
+        // The pump expires while the counters and the equipment still apply
+        cleanup_step(&mut game);
+        let creature = game.get_card(creature_id).unwrap();
+        assert_eq!(creature.state.power.current, 4);
+        assert_eq!(creature.state.toughness.current, 4);
+    }
+
+    #[test]
+    fn test_pump_attacking_creature() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        // The combat trick is cast after the attacker is declared
+        let card_id = create_spell(
+            &mut game,
+            Card::new_instant(player_id),
+            "G",
+            Effect::Pump(3, 3),
+            Target::Creature,
+        );
+        fast_declare_attacker(&mut game, attacker_id);
+        add_mana(&mut game, player_id, Mana::from("G"));
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("G"));
+        action.choices.target = Choice::Card(attacker_id);
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        fast_declare_blockers(&mut game, &[], attacker_id);
+        combat_damage_step_start(&mut game);
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 15);
+    }
+
+    #[test]
+    fn test_modal_spell() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        create_library(&mut game, player_id, 2);
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        let modes = vec![
+            Mode {
+                effect: Effect::Damage(Amount::Fixed(2)),
+                target: Target::Creature,
+            },
+            Mode {
+                effect: Effect::Draw(Amount::Fixed(1)),
+                target: Target::Owner,
+            },
+            Mode {
+                effect: Effect::LoseLife(Amount::Fixed(3)),
+                target: Target::Player,
+            },
+        ];
+        let card_id = create_sorcery(&mut game, player_id, Effect::Modal(2, modes), Target::None);
+
+        let creature = Choice::Card(creature_id);
+        let opponent = Choice::Player(opponent_id);
+        let invalid = [
+            (vec![0], vec![creature.clone()]),
+            (vec![0, 0], vec![creature.clone(), creature.clone()]),
+            (vec![0, 3], vec![creature.clone(), opponent.clone()]),
+            (vec![0, 2], vec![creature.clone()]),
+            (vec![0, 2], vec![opponent.clone(), creature.clone()]),
+        ];
+        for (modes, choices) in invalid {
+            let mut action =
+                create_sorcery_action(&mut game, card_id, player_id, Choice::And(choices));
+            action.choices.modes = modes;
+            assert!(!action.valid(&mut game));
+        }
+
+        // Each target is chosen in the order of the chosen modes
+        let choice = Choice::And(vec![opponent, creature]);
+        let mut action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        action.choices.modes = vec![2, 0];
+        assert!(action.valid(&mut game));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 17);
+        assert_eq!(game.get_player(player_id).unwrap().hand.len(), 0);
+    }
+
+    #[test]
+    fn test_modal_spell_mode_with_choice() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        create_library(&mut game, player_id, 1);
+        let opponent_card = game.add_card(Card::new_land(opponent_id));
+        put_in_hand(&mut game, opponent_card);
+
+        let modes = vec![
+            Mode {
+                effect: Effect::Discard(Amount::Fixed(1)),
+                target: Target::Player,
+            },
+            Mode {
+                effect: Effect::Draw(Amount::Fixed(1)),
+                target: Target::Owner,
+            },
+        ];
+        let card_id = create_sorcery(&mut game, player_id, Effect::Modal(2, modes), Target::None);
+
+        // Modes targeting their owner do not require a target choice
+        let choice = Choice::And(vec![Choice::None, Choice::Player(opponent_id)]);
+        let mut action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        action.choices.modes = vec![1, 0];
+        play_card(&mut game, card_id, action);
+
+        start_resolve(&mut game);
+        let mut next_choice = resolve_choice(&mut game, ResolveChoice::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(game.get_player(player_id).unwrap().hand.len(), 1);
+        assert_eq!(
+            next_choice,
+            ResolveChoice {
+                player_id: opponent_id,
+                effect: Effect::Discard(Amount::Fixed(1)),
+                choice: Choice::None
+            }
+        );
+
+        next_choice.choice = Choice::Card(opponent_card);
+        assert_eq!(resolve_choice(&mut game, next_choice), Ok(None));
+        end_resolve(&mut game);
+        assert_eq!(game.get_card(opponent_card).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_conditional_effect() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let effect = Effect::If(
+            EffectCondition::Control(Filter::Quality(Quality::Subtype(CardSubtype::Forest))),
+            Box::new(Effect::Damage(Amount::Fixed(4))),
+            Box::new(Effect::Damage(Amount::Fixed(2))),
+        );
+        let opponent = Choice::Player(opponent_id);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            effect.clone(),
+            Target::Player,
+            opponent.clone(),
+        );
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+
+        let mut card = Card::new_land(player_id);
+        card.subtypes.insert(CardSubtype::Forest);
+        let forest_id = game.add_card(card);
+        put_on_battlefield(&mut game, forest_id);
+
+        cast_sorcery(&mut game, player_id, effect, Target::Player, opponent);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 14);
+    }
+
+    #[test]
+    fn test_condition_checked_as_effect_resolves() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(player_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        // The creature is destroyed before the condition is checked
+        let effect = Effect::And(VecDeque::from([
+            Effect::Destroy,
+            Effect::If(
+                EffectCondition::Control(Filter::Quality(Quality::Type(CardType::Creature))),
+                Box::new(Effect::None),
+                Box::new(Effect::And(VecDeque::from([
+                    Effect::GainLife(Amount::Fixed(1)),
+                    Effect::GainLife(Amount::Fixed(2)),
+                ]))),
+            ),
+        ]));
+        cast_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_player(player_id).unwrap().life, 23);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 20);
+    }
+
+    #[test]
+    fn test_x_spell() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_sorcery(player_id);
+        card.cost = Cost::Mana("XR");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::X),
+            target: Target::Player,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("3R"));
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("3R"));
+        action.choices.target = Choice::Player(opponent_id);
+        action.choices.x = 4;
+        assert!(!action.valid(&mut game));
+
+        action.choices.x = 3;
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 17);
+    }
+
+    #[test]
+    fn test_amounts_computed_from_game_state() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        create_library(&mut game, player_id, 3);
+        for _ in 0..2 {
+            let creature_id = game.add_card(Card::new_creature(player_id, 2, 2));
+            put_on_battlefield(&mut game, creature_id);
+        }
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Draw(Amount::Permanents(Filter::And(&[
+                Filter::Controller(Controller::You),
+                Filter::Quality(Quality::Type(CardType::Creature)),
+            ]))),
+            Target::Owner,
+            Choice::Player(player_id),
+        );
+        assert_eq!(game.get_player(player_id).unwrap().hand.len(), 2);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Damage(Amount::CardsInHand),
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+
+        // The amount is computed after the previous effects have resolved
+        let effect = Effect::And(VecDeque::from([
+            Effect::GainLife(Amount::Fixed(3)),
+            Effect::LoseLife(Amount::LifeGained),
+        ]));
+        cast_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::Player,
+            Choice::Player(opponent_id),
+        );
+        assert_eq!(game.get_player(player_id).unwrap().life, 23);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 15);
+    }
+
+    #[test]
+    fn test_unevaluated_amount_fails_to_resolve() {
+        let (mut game, player_id, _) = Game::new();
+        let action = Action::new(player_id, 0);
+
+        let effect = Effect::GainLife(Amount::CardsInHand);
+        assert_eq!(
+            resolve_effect(&mut game, &effect, &action, ResolveChoice::default()),
+            Err(ResolveError::UnevaluatedAmount)
+        );
+
+        let effect = effect.evaluate(&mut game, &action);
+        assert_eq!(
+            resolve_effect(&mut game, &effect, &action, ResolveChoice::default()),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_damage_equal_to_source_power() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::Tap(Target::Source),
+            effect: Effect::Damage(Amount::SourcePower),
+            target: Target::Player,
+            restrictions: vec![],
+        });
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+        let creature = game.get_card(card_id).unwrap();
+        creature.state.summoning_sickness.current = false;
+        creature.state.add_counters(Counter::Plus, 2);
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.cost = Choice::Card(card_id);
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 15);
+    }
+
+    #[test]
+    fn test_damage_equal_to_sacrificed_source_power() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::Sacrifice(Target::Source),
+            effect: Effect::Damage(Amount::SourcePower),
+            target: Target::Player,
+            restrictions: vec![],
+        });
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+        game.get_card(card_id)
+            .unwrap()
+            .state
+            .add_counters(Counter::Plus, 2);
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.cost = Choice::Card(card_id);
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Graveyard);
+
+        // The damage uses the power of the source as it last existed on the battlefield
+        resolve_auto(&mut game);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 15);
+    }
+
+    #[test]
+    fn test_up_to_two_target_creatures() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creatures: Vec<ObjectId> = (0..3)
+            .map(|_| {
+                let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+                put_on_battlefield(&mut game, creature_id);
+                creature_id
+            })
+            .collect();
+        const SLOTS: &[TargetSlot] = &[TargetSlot {
+            target: Target::Creature,
+            min: 0,
+            max: 2,
+        }];
+        let card_id = create_sorcery(&mut game, player_id, Effect::Destroy, Target::Slots(SLOTS));
+
+        let chosen = |ids: &[ObjectId]| {
+            Choice::And(vec![Choice::And(
+                ids.iter().map(|card_id| Choice::Card(*card_id)).collect(),
+            )])
+        };
+        let invalid = [
+            chosen(&creatures),
+            chosen(&[creatures[0], creatures[0]]),
+            Choice::And(vec![Choice::Player(opponent_id)]),
+            Choice::And(vec![]),
+        ];
+        for choice in invalid {
+            let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+            assert!(!action.valid(&mut game));
+        }
+
+        let choice = Choice::And(vec![Choice::None]);
+        let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        assert!(action.valid(&mut game));
+
+        let choice = chosen(&[creatures[0], creatures[2]]);
+        let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        let zones: Vec<Zone> = creatures
+            .iter()
+            .map(|card_id| game.get_card(*card_id).unwrap().zone.clone())
+            .collect();
+        assert_eq!(
+            zones,
+            vec![Zone::Graveyard, Zone::Battlefield, Zone::Graveyard]
+        );
+    }
+
+    #[test]
+    fn test_target_creature_and_target_player() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+        const SLOTS: &[TargetSlot] = &[
+            TargetSlot {
+                target: Target::Creature,
+                min: 1,
+                max: 1,
+            },
+            TargetSlot {
+                target: Target::Player,
+                min: 1,
+                max: 1,
+            },
+        ];
+        let effect = Effect::Damage(Amount::Fixed(2));
+        let card_id = create_sorcery(&mut game, player_id, effect, Target::Slots(SLOTS));
+
+        // Each slot validates its own targets
+        let swapped = Choice::And(vec![Choice::Player(opponent_id), Choice::Card(creature_id)]);
+        let action = create_sorcery_action(&mut game, card_id, player_id, swapped);
+        assert!(!action.valid(&mut game));
+
+        let choice = Choice::And(vec![Choice::Card(creature_id), Choice::Player(opponent_id)]);
+        let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+    }
+
+    #[test]
+    fn test_divided_damage() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 3, 3));
+        put_on_battlefield(&mut game, creature_id);
+        const SLOTS: &[TargetSlot] = &[TargetSlot {
+            target: Target::AnyOf(&[Target::Player, Target::Creature]),
+            min: 1,
+            max: 4,
+        }];
+        let effect = Effect::DividedDamage(Amount::Fixed(4));
+        let card_id = create_sorcery(&mut game, player_id, effect, Target::Slots(SLOTS));
+
+        let targets = Choice::And(vec![Choice::And(vec![
+            Choice::Card(creature_id),
+            Choice::Player(opponent_id),
+        ])]);
+        for division in [vec![], vec![4], vec![2, 1], vec![4, 0], vec![2, 1, 1]] {
+            let mut action = create_sorcery_action(&mut game, card_id, player_id, targets.clone());
+            action.choices.division = division;
+            assert!(!action.valid(&mut game));
+        }
+
+        let mut action = create_sorcery_action(&mut game, card_id, player_id, targets);
+        action.choices.division = vec![3, 1];
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+    }
+
+    #[test]
+    fn test_spell_countered_without_legal_targets() {
+        for target in [Target::Creature, Target::Card] {
+            let (mut game, player_id, opponent_id) = Game::new();
+            let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+            put_on_battlefield(&mut game, creature_id);
+
+            let effect = Effect::And(VecDeque::from([
+                Effect::Destroy,
+                Effect::GainLife(Amount::Fixed(3)),
+            ]));
+            play_sorcery(
+                &mut game,
+                player_id,
+                effect,
+                target,
+                Choice::Card(creature_id),
+            );
+            assert_eq!(game.stack.len(), 1);
+
+            // The target leaves the battlefield before the spell resolves
+            put_in_hand(&mut game, creature_id);
+            let action = game.stack[0].action.clone();
+            assert_eq!(action.count_legal_targets(&mut game), (0, 1));
+            resolve_auto(&mut game);
+
+            assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Hand);
+            let player = game.get_player(player_id).unwrap();
+            assert_eq!(player.graveyard.len(), 1);
+            assert_eq!(player.life, 20);
+        }
+    }
+
+    #[test]
+    fn test_illegal_targets_are_skipped() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creatures: Vec<ObjectId> = (0..2)
+            .map(|_| {
+                let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+                put_on_battlefield(&mut game, creature_id);
+                creature_id
+            })
+            .collect();
+        const SLOTS: &[TargetSlot] = &[
+            TargetSlot {
+                target: Target::Creature,
+                min: 1,
+                max: 1,
+            },
+            TargetSlot {
+                target: Target::Player,
+                min: 1,
+                max: 1,
+            },
+        ];
+        let effect = Effect::Damage(Amount::Fixed(2));
+        let card_id = create_sorcery(&mut game, player_id, effect, Target::Slots(SLOTS));
+        let choice = Choice::And(vec![
+            Choice::Card(creatures[0]),
+            Choice::Player(opponent_id),
+        ]);
+        let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+        play_card(&mut game, card_id, action);
+
+        put_in_exile(&mut game, creatures[0]);
+        resolve_auto(&mut game);
+        assert_eq!(game.get_card(creatures[0]).unwrap().zone, Zone::Exile);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+
+        const DIVIDED: &[TargetSlot] = &[TargetSlot {
+            target: Target::Creature,
+            min: 1,
+            max: 2,
+        }];
+        let effect = Effect::DividedDamage(Amount::Fixed(4));
+        let card_id = create_sorcery(&mut game, player_id, effect, Target::Slots(DIVIDED));
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 3, 3));
+        put_on_battlefield(&mut game, creature_id);
+        let targets = Choice::And(vec![Choice::And(vec![
+            Choice::Card(creatures[1]),
+            Choice::Card(creature_id),
+        ])]);
+        let mut action = create_sorcery_action(&mut game, card_id, player_id, targets);
+        action.choices.division = vec![2, 2];
+        play_card(&mut game, card_id, action);
+
+        put_in_hand(&mut game, creatures[1]);
+        resolve_auto(&mut game);
+        let creature = game.get_card(creature_id).unwrap();
+        assert_eq!(creature.zone, Zone::Battlefield);
+        assert_eq!(creature.state.damage, 2);
+    }
+
+    #[test]
+    fn test_filtered_target() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut creatures = vec![];
+        for (owner_id, power, tapped) in [
+            (player_id, 2, true),
+            (opponent_id, 2, false),
+            (opponent_id, 3, true),
+            (opponent_id, 2, true),
+        ] {
+            let creature_id = game.add_card(Card::new_creature(owner_id, power, 2));
+            put_on_battlefield(&mut game, creature_id);
+            game.get_card(creature_id).unwrap().state.tapped.current = tapped;
+            creatures.push(creature_id);
+        }
+        let graveyard_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_graveyard(&mut game, graveyard_id);
+        game.get_card(graveyard_id).unwrap().state.tapped.current = true;
+
+        // Destroy target tapped creature an opponent controls with power 2 or less
+        let mut card = Card::new_sorcery(player_id);
+        card.cost = Cost::Mana("B");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Destroy,
+            target: Target::Filtered(Filter::And(&[
+                Filter::Zone(Zone::Battlefield),
+                Filter::Quality(Quality::Type(CardType::Creature)),
+                Filter::Controller(Controller::Opponent),
+                Filter::Tapped,
+                Filter::Power(Comparison::LessOrEqual, 2),
+            ])),
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("B"));
+
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("B"));
+        for target_id in [creatures[0], creatures[1], creatures[2], graveyard_id] {
+            action.choices.target = Choice::Card(target_id);
+            assert!(!action.valid(&mut game));
+        }
+
+        action.choices.target = Choice::Card(creatures[3]);
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+        assert_eq!(game.get_card(creatures[3]).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_each_permanent_matching_combined_filter() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut creatures = vec![];
+        for cost in ["G", "3R", "R"] {
+            let mut card = Card::new_creature(opponent_id, 2, 2);
+            card.cost = Cost::Mana(cost);
+            let creature_id = game.add_card(card);
+            put_on_battlefield(&mut game, creature_id);
+            creatures.push(creature_id);
+        }
+
+        // Destroy each creature that is neither green nor with mana value 3 or greater
+        let effect = Effect::All(
+            Filter::And(&[
+                Filter::Quality(Quality::Type(CardType::Creature)),
+                Filter::Not(&Filter::Or(&[
+                    Filter::Quality(Quality::Color(Color::Green)),
+                    Filter::ManaValue(Comparison::GreaterOrEqual, 3),
+                ])),
+            ]),
+            Box::new(Effect::Destroy),
+        );
+        cast_sorcery(&mut game, player_id, effect, Target::None, Choice::None);
+
+        let zones: Vec<Zone> = creatures
+            .iter()
+            .map(|card_id| game.get_card(*card_id).unwrap().zone.clone())
+            .collect();
+        assert_eq!(
+            zones,
+            vec![Zone::Battlefield, Zone::Battlefield, Zone::Graveyard]
+        );
+    }
+
+    #[test]
+    fn test_filter_another_permanent() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::None,
+            effect: Effect::Damage(Amount::Permanents(Filter::And(&[
+                Filter::Another,
+                Filter::Controller(Controller::You),
+                Filter::Toughness(Comparison::Greater, 1),
+            ]))),
+            target: Target::Player,
+            restrictions: vec![],
+        });
+        let source_id = game.add_card(card);
+        put_on_battlefield(&mut game, source_id);
+        for (owner_id, toughness) in [
+            (player_id, 2),
+            (player_id, 3),
+            (player_id, 1),
+            (opponent_id, 2),
+        ] {
+            let creature_id = game.add_card(Card::new_creature(owner_id, 1, toughness));
+            put_on_battlefield(&mut game, creature_id);
+        }
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, source_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, source_id, 0, action));
+        resolve_auto(&mut game);
+
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+    }
+
+    #[test]
+    fn test_filtered_target_defaults_to_battlefield() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+        let graveyard_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_graveyard(&mut game, graveyard_id);
+
+        // Destroy target creature an opponent controls
+        let target = Target::Filtered(Filter::And(&[
+            Filter::Quality(Quality::Type(CardType::Creature)),
+            Filter::Controller(Controller::Opponent),
+        ]));
+        let card_id = create_sorcery(&mut game, player_id, Effect::Destroy, target);
+        let action =
+            create_sorcery_action(&mut game, card_id, player_id, Choice::Card(graveyard_id));
+        assert!(!action.valid(&mut game));
+
+        let action =
+            create_sorcery_action(&mut game, card_id, player_id, Choice::Card(creature_id));
+        play_card(&mut game, card_id, action);
+
+        // The target leaves the battlefield before the spell resolves
+        put_in_hand(&mut game, creature_id);
+        resolve_auto(&mut game);
+        assert_eq!(game.get_card(creature_id).unwrap().zone, Zone::Hand);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_filtered_target_with_zone_in_one_branch() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let mut cards = vec![];
+        for zone in [
+            Zone::Battlefield,
+            Zone::Graveyard,
+            Zone::Hand,
+            Zone::Library,
+        ] {
+            let card_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+            match zone {
+                Zone::Battlefield => put_on_battlefield(&mut game, card_id),
+                Zone::Graveyard => put_on_graveyard(&mut game, card_id),
+                Zone::Hand => put_in_hand(&mut game, card_id),
+                _ => put_on_deck_top(&mut game, card_id, opponent_id),
+            }
+            cards.push(card_id);
+        }
+        let artifact_id = game.add_card(Card::new_artifact(opponent_id));
+        put_on_battlefield(&mut game, artifact_id);
+
+        // Exile target card in a graveyard or target creature
+        let target = Target::Filtered(Filter::Or(&[
+            Filter::Zone(Zone::Graveyard),
+            Filter::Quality(Quality::Type(CardType::Creature)),
+        ]));
+        let card_id = create_sorcery(&mut game, player_id, Effect::Exile, target);
+        for (target_id, valid) in [
+            (cards[0], true),
+            (cards[1], true),
+            (cards[2], false),
+            (cards[3], false),
+            (artifact_id, false),
+        ] {
+            let action =
+                create_sorcery_action(&mut game, card_id, player_id, Choice::Card(target_id));
+            assert_eq!(action.valid(&mut game), valid);
+        }
+    }
+
+    #[test]
+    fn test_power_filter_matches_only_creatures() {
+        let (mut game, player_id, _) = Game::new();
+        let artifact_id = game.add_card(Card::new_artifact(player_id));
+        put_on_battlefield(&mut game, artifact_id);
+        let creature_id = game.add_card(Card::new_creature(player_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        for filter in [
+            Filter::Power(Comparison::LessOrEqual, 2),
+            Filter::Toughness(Comparison::LessOrEqual, 2),
+        ] {
+            assert!(!filter.matches(&mut game, artifact_id, artifact_id, player_id));
+            assert!(filter.matches(&mut game, creature_id, artifact_id, player_id));
+        }
+    }
+
+    #[test]
+    fn test_filtered_player_target() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        // Target opponent loses 2 life
+        let target = Target::Filtered(Filter::And(&[
+            Filter::Player,
+            Filter::Controller(Controller::Opponent),
+        ]));
+        let effect = Effect::LoseLife(Amount::Fixed(2));
+        let card_id = create_sorcery(&mut game, player_id, effect, target);
+        for choice in [Choice::Player(player_id), Choice::Card(creature_id)] {
+            let action = create_sorcery_action(&mut game, card_id, player_id, choice);
+            assert!(!action.valid(&mut game));
+        }
+
+        let action =
+            create_sorcery_action(&mut game, card_id, player_id, Choice::Player(opponent_id));
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+    }
+
+    #[test]
+    fn test_sacrifice_filtered_cost() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        // Sacrifice another artifact
+        let card_id = create_pinger(
+            &mut game,
+            player_id,
+            Cost::Sacrifice(Target::Filtered(Filter::And(&[
+                Filter::Another,
+                Filter::Quality(Quality::Type(CardType::Artifact)),
+            ]))),
+        );
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+        let artifact_id = game.add_card(Card::new_artifact(player_id));
+        put_on_battlefield(&mut game, artifact_id);
+
+        upkeep_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        for cost_id in [card_id, creature_id] {
+            action.choices.cost = Choice::Card(cost_id);
+            assert!(!action.valid(&mut game));
+        }
+
+        action.choices.cost = Choice::Card(artifact_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        assert_eq!(game.get_card(artifact_id).unwrap().zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_filtered_trigger_condition() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        // Whenever a creature an opponent controls dies
+        create_watcher(
+            &mut game,
+            player_id,
+            Condition::Dies(Target::Filtered(Filter::And(&[
+                Filter::Quality(Quality::Type(CardType::Creature)),
+                Filter::Controller(Controller::Opponent),
+            ]))),
+        );
+        let own_id = game.add_card(Card::new_creature(player_id, 2, 2));
+        put_on_battlefield(&mut game, own_id);
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Destroy,
+            Target::Creature,
+            Choice::Card(own_id),
+        );
+        assert!(game.stack.is_empty());
+
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Destroy,
+            Target::Creature,
+            Choice::Card(creature_id),
+        );
+        assert_eq!(game.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_target_opponent_or_planeswalker() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let planeswalker_id = create_planeswalker(&mut game, opponent_id, 3);
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+
+        let mut card = Card::new_instant(player_id);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::LoseLife(Amount::Fixed(2)),
+            target: Target::Opponent,
+        });
+        let opponent_spell = game.add_card(card);
+        put_in_hand(&mut game, opponent_spell);
+
+        let mut card = Card::new_instant(player_id);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(2)),
+            target: Target::AnyOf(&[Target::Player, Target::Planeswalker]),
+        });
+        let burn_id = game.add_card(card);
+        put_in_hand(&mut game, burn_id);
+
+        precombat_step(&mut game);
+        let mut action = create_card_action(&mut game, opponent_spell, player_id).unwrap();
+        action.choices.target = Choice::Player(player_id);
+        assert!(!action.valid(&mut game));
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(action.valid(&mut game));
+
+        let mut action = create_card_action(&mut game, burn_id, player_id).unwrap();
+        action.choices.target = Choice::Card(creature_id);
+        assert!(!action.valid(&mut game));
+        action.choices.target = Choice::Card(planeswalker_id);
+        play_card(&mut game, burn_id, action);
+        resolve_auto(&mut game);
+
+        let planeswalker = game.get_card(planeswalker_id).unwrap();
+        assert_eq!(planeswalker.state.get_counter(Counter::Loyalty), 1);
+    }
+
+    #[test]
+    fn test_any_target() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let planeswalker_id = create_planeswalker(&mut game, opponent_id, 3);
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, creature_id);
+        let land_id = game.add_card(Card::new_land(opponent_id));
+        put_on_battlefield(&mut game, land_id);
+
+        let mut card = Card::new_instant(player_id);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(1)),
+            target: Target::Any,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        precombat_step(&mut game);
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        for choice in [
+            Choice::Player(opponent_id),
+            Choice::Card(creature_id),
+            Choice::Card(planeswalker_id),
+        ] {
+            action.choices.target = choice;
+            assert!(action.valid(&mut game));
+        }
+        action.choices.target = Choice::Card(land_id);
+        assert!(!action.valid(&mut game));
+    }
+
+    #[test]
+    fn test_each_player_draws_and_discards() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let third_id = game.add_player(Player::new());
+        let players = [player_id, opponent_id, third_id];
+        for player_id in players {
+            create_library(&mut game, player_id, 1);
+        }
+
+        let effect = Effect::And(VecDeque::from([
+            Effect::Draw(Amount::Fixed(1)),
+            Effect::Discard(Amount::Fixed(1)),
+        ]));
+        play_sorcery(
+            &mut game,
+            player_id,
+            effect,
+            Target::EachPlayer,
+            Choice::None,
+        );
+
+        start_resolve(&mut game);
+        let mut next_choice = resolve_choice(&mut game, ResolveChoice::default()).unwrap();
+        for player_id in players {
+            assert_eq!(game.get_player(player_id).unwrap().hand.len(), 1);
+        }
+
+        // Each player discards in APNAP order
+        for player_id in players {
+            let mut choice = next_choice.unwrap();
+            assert_eq!(choice.player_id, player_id);
+            let card_id = *game.get_player(player_id).unwrap().hand.first().unwrap();
+            choice.choice = Choice::Card(card_id);
+            next_choice = resolve_choice(&mut game, choice).unwrap();
+        }
+        assert!(next_choice.is_none());
+        end_resolve(&mut game);
+
+        for player_id in players {
+            assert_eq!(game.get_player(player_id).unwrap().hand.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_damage_each_opponent() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let third_id = game.add_player(Player::new());
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::Damage(Amount::Fixed(2)),
+            Target::EachOpponent,
+            Choice::None,
+        );
+
+        assert_eq!(game.get_player(player_id).unwrap().life, 20);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 18);
+        assert_eq!(game.get_player(third_id).unwrap().life, 18);
+    }
+
+    #[test]
+    fn test_each_player_gains_life() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let third_id = game.add_player(Player::new());
+        cast_sorcery(
+            &mut game,
+            player_id,
+            Effect::GainLife(Amount::Fixed(2)),
+            Target::EachPlayer,
+            Choice::None,
+        );
+
+        assert_eq!(game.get_player(player_id).unwrap().life, 22);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 22);
+        assert_eq!(game.get_player(third_id).unwrap().life, 22);
+    }
+
+    fn create_planeswalker(game: &mut Game, player_id: ObjectId, loyalty: u16) -> ObjectId {
+        let mut card = Card::new_planeswalker(player_id, loyalty);
+        for cost in [Cost::Loyalty(1), Cost::Loyalty(-3)] {
+            card.activated_abilities.push(ActivatedAbility {
+                cost,
+                effect: Effect::Damage(Amount::Fixed(1)),
+                target: Target::Player,
+                restrictions: vec![],
+            });
+        }
+        let card_id = game.add_card(card);
+        put_on_battlefield(game, card_id);
+        card_id
+    }
+
+    #[test]
+    fn test_planeswalker_enters_with_loyalty() {
+        let (mut game, player_id, _) = Game::new();
+        let card_id = create_planeswalker(&mut game, player_id, 3);
+
+        let card = game.get_card(card_id).unwrap();
+        assert_eq!(card.state.get_counter(Counter::Loyalty), 3);
+    }
+
+    #[test]
+    fn test_activate_loyalty_ability() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_planeswalker(&mut game, player_id, 2);
+
+        // Loyalty abilities can be activated only at sorcery speed
+        upkeep_step(&mut game);
+        assert!(create_ability_action(&mut game, player_id, card_id, 0).is_none());
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 1).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(!action.valid(&mut game));
+
+        let mut action = create_ability_action(&mut game, player_id, card_id, 0).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 0, action));
+        resolve_auto(&mut game);
+
+        let card = game.get_card(card_id).unwrap();
+        assert_eq!(card.state.get_counter(Counter::Loyalty), 3);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+
+        // Only one loyalty ability of the permanent can be activated each turn
+        assert!(create_ability_action(&mut game, player_id, card_id, 1).is_none());
+    }
+
+    #[test]
+    fn test_planeswalker_dies_without_loyalty() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_planeswalker(&mut game, player_id, 3);
+
+        precombat_step(&mut game);
+        let mut action = create_ability_action(&mut game, player_id, card_id, 1).unwrap();
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(play_ability(&mut game, card_id, 1, action));
+        resolve_auto(&mut game);
+
+        let card = game.get_card(card_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+        assert_eq!(game.get_player(opponent_id).unwrap().life, 19);
+    }
+
+    #[test]
+    fn test_damage_removes_loyalty() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let card_id = create_planeswalker(&mut game, opponent_id, 3);
+        let source_id = game.add_card(Card::new_creature(player_id, 2, 2));
+
+        assert_eq!(deal_damage(&mut game, source_id, card_id, 2), 2);
+        let card = game.get_card(card_id).unwrap();
+        assert_eq!(card.state.get_counter(Counter::Loyalty), 1);
+        assert_eq!(card.zone, Zone::Battlefield);
+    }
+
+    #[test]
+    fn test_flying() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.subtypes.insert(CardSubtype::Dragon);
+        card.static_abilities.insert(StaticAbility::Flying);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let mut card = Card::new_creature(opponent_id, 1, 1);
+        card.subtypes.insert(CardSubtype::Human);
+        let human_id = game.add_card(card);
+        put_on_battlefield(&mut game, human_id);
+
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.subtypes.insert(CardSubtype::Spider);
+        card.static_abilities.insert(StaticAbility::Reach);
+        let spider_id = game.add_card(card);
+        put_on_battlefield(&mut game, spider_id);
+
+        let mut card = Card::new_creature(opponent_id, 1, 1);
         card.subtypes.insert(CardSubtype::Bird);
         card.static_abilities.insert(StaticAbility::Flying);
         let bird_id = game.add_card(card);
         put_on_battlefield(&mut game, bird_id);
 
-        declare_attackers_step_start(&mut game);
-        declare_attacker(&mut game, attacker_id, opponent_id);
-        declare_attackers_step_end(&mut game);
-        declare_blockers_step_start(&mut game);
+        declare_attackers_step_start(&mut game);
+        declare_attacker(&mut game, attacker_id, opponent_id);
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+
+        assert!(can_declare_blocker(&mut game, spider_id, attacker_id));
+        assert!(can_declare_blocker(&mut game, bird_id, attacker_id));
+        assert!(!can_declare_blocker(&mut game, human_id, attacker_id));
+    }
+
+    #[test]
+    fn test_vigilance() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.subtypes.insert(CardSubtype::Human);
+        card.static_abilities.insert(StaticAbility::Vigilance);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        declare_attackers_step_start(&mut game);
+        declare_attacker(&mut game, attacker_id, opponent_id);
+        declare_attackers_step_end(&mut game);
+
+        let attacker = game.get_card(attacker_id).unwrap();
+        assert!(!attacker.state.tapped.current);
+    }
+
+    #[test]
+    fn test_defender() {
+        let (mut game, player_id, _) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 0, 4);
+        card.subtypes.insert(CardSubtype::Spirit);
+        card.static_abilities.insert(StaticAbility::Defender);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        assert!(!can_declare_attacker(&mut game, attacker_id));
+    }
+
+    #[test]
+    fn test_first_strike_attacks_first() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 1);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 4, 2));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 1);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_first_strike_blocks_first() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 4, 2);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let mut card = Card::new_creature(opponent_id, 2, 1);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 1);
+    }
+
+    #[test]
+    fn test_first_strike_simultaneous() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 3, 2);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let mut card = Card::new_creature(opponent_id, 2, 3);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_first_strike_instant_after_priority() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 2, 3));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_declare_attacker(&mut game, attacker_id);
+        fast_declare_blockers(&mut game, &[blocker_id], attacker_id);
+        combat_damage_step_start(&mut game);
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+
+        let mut card = Card::new_instant(player_id);
+        card.cost = Cost::Mana("R");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(2)),
+            target: Target::AnyOf(&[Target::Player, Target::Creature]),
+        });
+        let shock = game.add_card(card);
+        add_mana(&mut game, player_id, Mana::from("R"));
+
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.target = Choice::Card(blocker_id);
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+
+        play_card(&mut game, shock, action);
+        resolve_auto(&mut game);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_double_strike() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 1);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 4, 4));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_double_strike_attacks_first() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 4, 3);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 3);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_double_strike_player_damage() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 4, 3);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        fast_combat(&mut game, attacker_id, &[]);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 12);
+    }
+
+    #[test]
+    fn test_double_strike_multiple_blockers() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_one = game.add_card(Card::new_creature(opponent_id, 1, 4));
+        put_on_battlefield(&mut game, blocker_one);
+
+        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 4));
+        put_on_battlefield(&mut game, blocker_two);
+
+        fast_declare_attacker(&mut game, attacker_id);
+        declare_blockers_step_start(&mut game);
+        declare_blocker(&mut game, blocker_one, attacker_id);
+        declare_blocker(&mut game, blocker_two, attacker_id);
+        declare_blockers_step_end(&mut game);
+        combat_damage_step_start(&mut game);
+        reset_combat_assignments(&mut game, attacker_id);
+
+        assert!(assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_two,
+            AttackType::FirstStrike,
+            2
+        ));
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+
+        assert!(assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_two,
+            AttackType::Regular,
+            2
+        ));
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_one).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 4);
+
+        let card = game.get_card(blocker_two).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 20);
+    }
+
+    #[test]
+    fn test_double_strike_multiple_blockers_auto_assign() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_one = game.add_card(Card::new_creature(opponent_id, 1, 2));
+        put_on_battlefield(&mut game, blocker_one);
+
+        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
+        put_on_battlefield(&mut game, blocker_two);
+
+        fast_combat(&mut game, attacker_id, &[blocker_one, blocker_two]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 1);
+
+        let card = game.get_card(blocker_one).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_two).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 20);
+    }
+
+    #[test]
+    fn test_double_strike_blocked_by_first_strike_auto_assign() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let mut card = Card::new_creature(opponent_id, 1, 2);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let blocker_one = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_one);
 
-        assert!(can_declare_blocker(&mut game, spider_id, attacker_id));
-        assert!(can_declare_blocker(&mut game, bird_id, attacker_id));
-        assert!(!can_declare_blocker(&mut game, human_id, attacker_id));
+        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
+        put_on_battlefield(&mut game, blocker_two);
+
+        fast_combat(&mut game, attacker_id, &[blocker_one, blocker_two]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_one).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_two).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 20);
     }
 
     #[test]
-    fn test_vigilance() {
+    fn test_double_strike_blocked_by_first_strike() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 1, 1);
-        card.subtypes.insert(CardSubtype::Human);
-        card.static_abilities.insert(StaticAbility::Vigilance);
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        declare_attackers_step_start(&mut game);
-        declare_attacker(&mut game, attacker_id, opponent_id);
-        declare_attackers_step_end(&mut game);
+        let mut card = Card::new_creature(opponent_id, 1, 2);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let blocker_one = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_one);
 
-        let attacker = game.get_card(attacker_id).unwrap();
-        assert!(!attacker.state.tapped.current);
+        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
+        put_on_battlefield(&mut game, blocker_two);
+
+        fast_declare_attacker(&mut game, attacker_id);
+        fast_declare_blockers(&mut game, &[blocker_one, blocker_two], attacker_id);
+        combat_damage_step_start(&mut game);
+        reset_combat_assignments(&mut game, attacker_id);
+
+        assert!(assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_two,
+            AttackType::FirstStrike,
+            2,
+        ));
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+
+        assert!(assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_one,
+            AttackType::Regular,
+            2
+        ));
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 1);
+
+        let card = game.get_card(blocker_one).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let card = game.get_card(blocker_two).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
     }
 
     #[test]
-    fn test_defender() {
-        let (mut game, player_id, _) = Game::new();
+    fn test_trample() {
+        let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 0, 4);
-        card.subtypes.insert(CardSubtype::Spirit);
-        card.static_abilities.insert(StaticAbility::Defender);
+        let mut card = Card::new_creature(player_id, 4, 3);
+        card.static_abilities.insert(StaticAbility::Trample);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        assert!(!can_declare_attacker(&mut game, attacker_id));
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 2);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 17);
     }
 
     #[test]
-    fn test_first_strike_attacks_first() {
+    fn test_trample_blocked() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 2, 1);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.static_abilities.insert(StaticAbility::Trample);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 4, 2));
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 3));
         put_on_battlefield(&mut game, blocker_id);
 
         fast_combat(&mut game, attacker_id, &[blocker_id]);
 
         let card = game.get_card(attacker_id).unwrap();
         assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 1);
+        assert_eq!(card.state.toughness.current, 2);
 
         let card = game.get_card(blocker_id).unwrap();
         assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 20);
     }
 
     #[test]
-    fn test_first_strike_blocks_first() {
+    fn test_trample_first_strike() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 4, 2);
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.static_abilities.insert(StaticAbility::Trample);
+        card.static_abilities.insert(StaticAbility::FirstStrike);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let mut card = Card::new_creature(opponent_id, 2, 1);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        let blocker_id = game.add_card(card);
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
         put_on_battlefield(&mut game, blocker_id);
 
         fast_combat(&mut game, attacker_id, &[blocker_id]);
 
         let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 3);
 
         let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 1);
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 18);
     }
 
     #[test]
-    fn test_first_strike_simultaneous() {
+    fn test_trample_double_strike_auto_assign() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 3, 2);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.static_abilities.insert(StaticAbility::Trample);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let mut card = Card::new_creature(opponent_id, 2, 3);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        let blocker_id = game.add_card(card);
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
         put_on_battlefield(&mut game, blocker_id);
 
         fast_combat(&mut game, attacker_id, &[blocker_id]);
 
         let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 3);
+
+        let card = game.get_card(blocker_id).unwrap();
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 15);
+    }
+
+    #[test]
+    fn test_trample_double_strike() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.static_abilities.insert(StaticAbility::Trample);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_declare_attacker(&mut game, attacker_id);
+        fast_declare_blockers(&mut game, &[blocker_id], attacker_id);
+        combat_damage_step_start(&mut game);
+        reset_combat_assignments(&mut game, attacker_id);
+
+        assert!(assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_id,
+            AttackType::FirstStrike,
+            1,
+        ));
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+
+        assert!(!assign_combat_damage(
+            &mut game,
+            attacker_id,
+            blocker_id,
+            AttackType::Regular,
+            1
+        ));
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.zone, Zone::Battlefield);
+        assert_eq!(card.state.toughness.current, 3);
+
+        let card = game.get_card(blocker_id).unwrap();
         assert_eq!(card.zone, Zone::Graveyard);
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 15);
+    }
+
+    #[test]
+    fn test_deathtouch() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.static_abilities.insert(StaticAbility::Deathtouch);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 8, 8));
+        put_on_battlefield(&mut game, blocker_id);
+
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+
+        let attacker = game.get_card(attacker_id).unwrap();
+        assert_eq!(attacker.zone, Zone::Graveyard);
+
+        let blocker = game.get_card(blocker_id).unwrap();
+        assert_eq!(blocker.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_deathtouch_multiple_blockers() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Deathtouch);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_one = game.add_card(Card::new_creature(opponent_id, 4, 6));
+        put_on_battlefield(&mut game, blocker_one);
+
+        let blocker_two = game.add_card(Card::new_creature(opponent_id, 3, 8));
+        put_on_battlefield(&mut game, blocker_two);
+
+        fast_declare_attacker(&mut game, attacker_id);
+        fast_declare_blockers(&mut game, &[blocker_one, blocker_two], attacker_id);
+        combat_damage_step_start(&mut game);
+        reset_combat_assignments(&mut game, attacker_id);
+        assign_combat_damage(&mut game, attacker_id, blocker_one, AttackType::Regular, 1);
+        assign_combat_damage(&mut game, attacker_id, blocker_two, AttackType::Regular, 1);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let attacker = game.get_card(attacker_id).unwrap();
+        assert_eq!(attacker.zone, Zone::Graveyard);
+
+        let blocker = game.get_card(blocker_one).unwrap();
+        assert_eq!(blocker.zone, Zone::Graveyard);
+
+        let blocker = game.get_card(blocker_one).unwrap();
+        assert_eq!(blocker.zone, Zone::Graveyard);
     }
 
     #[test]
-    fn test_first_strike_instant_after_priority() {
+    fn test_deathtouch_first_strike() {
         let (mut game, player_id, opponent_id) = Game::new();
 
         let mut card = Card::new_creature(player_id, 1, 1);
+        card.static_abilities.insert(StaticAbility::Deathtouch);
         card.static_abilities.insert(StaticAbility::FirstStrike);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 2, 3));
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 8, 8));
         put_on_battlefield(&mut game, blocker_id);
 
-        fast_declare_attacker(&mut game, attacker_id);
-        fast_declare_blockers(&mut game, &[blocker_id], attacker_id);
-        combat_damage_step_start(&mut game);
-        combat_damage_step_end(&mut game, AttackType::FirstStrike);
-
-        let mut card = Card::new_instant(player_id);
-        card.cost = Cost::Mana("R");
-        card.play_ability = Some(PlayAbility {
-            effect: Effect::Damage(2),
-            target: Target::AnyOf(&[Target::Player, Target::Creature]),
-        });
-        let shock = game.add_card(card);
-        add_mana(&mut game, player_id, Mana::from("R"));
-
-        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
-        action.choices.target = Choice::Card(blocker_id);
-        action.choices.cost = Choice::Mana(Mana::from("R"));
-
-        play_card(&mut game, shock, action);
-        resolve_auto(&mut game);
-        combat_damage_step_end(&mut game, AttackType::Regular);
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
+        let attacker = game.get_card(attacker_id).unwrap();
+        assert_eq!(attacker.zone, Zone::Battlefield);
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let blocker = game.get_card(blocker_id).unwrap();
+        assert_eq!(blocker.zone, Zone::Graveyard);
     }
 
     #[test]
-    fn test_double_strike() {
+    fn test_lifelink() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 2, 1);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.static_abilities.insert(StaticAbility::Lifelink);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 4, 4));
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 2));
         put_on_battlefield(&mut game, blocker_id);
 
         fast_combat(&mut game, attacker_id, &[blocker_id]);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
-
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let player = game.get_player(player_id).unwrap();
+        assert_eq!(player.life, 22);
     }
 
     #[test]
-    fn test_double_strike_attacks_first() {
+    fn test_lifelink_spell() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 4, 3);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_instant(player_id);
+        card.cost = Cost::Mana("B");
+        card.static_abilities.insert(StaticAbility::Lifelink);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(3)),
+            target: Target::Player,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
-        put_on_battlefield(&mut game, blocker_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("B"));
 
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("B"));
+        action.choices.target = Choice::Player(opponent_id);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 3);
+        play_card(&mut game, card_id, action);
+        resolve_auto(&mut game);
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 17);
+
+        let player = game.get_player(player_id).unwrap();
+        assert_eq!(player.life, 23);
     }
 
     #[test]
-    fn test_double_strike_player_damage() {
+    fn test_infect() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 4, 3);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Infect);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
@@ -1303,412 +6123,481 @@ mod tests {
         fast_combat(&mut game, attacker_id, &[]);
 
         let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 12);
+        assert_eq!(opponent.life, 20);
+        assert_eq!(opponent.poison, 2);
     }
 
     #[test]
-    fn test_double_strike_multiple_blockers() {
+    fn test_infect_lose_game() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 2, 2);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let mut card = Card::new_creature(player_id, 10, 1);
+        card.static_abilities.insert(StaticAbility::Infect);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_one = game.add_card(Card::new_creature(opponent_id, 1, 4));
-        put_on_battlefield(&mut game, blocker_one);
+        fast_combat(&mut game, attacker_id, &[]);
 
-        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 4));
-        put_on_battlefield(&mut game, blocker_two);
+        assert_eq!(game.status, GameStatus::Lose(opponent_id));
+    }
 
-        fast_declare_attacker(&mut game, attacker_id);
-        declare_blockers_step_start(&mut game);
-        declare_blocker(&mut game, blocker_one, attacker_id);
-        declare_blocker(&mut game, blocker_two, attacker_id);
-        declare_blockers_step_end(&mut game);
-        combat_damage_step_start(&mut game);
-        reset_combat_assignments(&mut game, attacker_id);
+    #[test]
+    fn test_wither() {
+        let (mut game, player_id, opponent_id) = Game::new();
 
-        assert!(assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_two,
-            AttackType::FirstStrike,
-            2
-        ));
-        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        let mut card = Card::new_creature(player_id, 2, 3);
+        card.static_abilities.insert(StaticAbility::Wither);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
 
-        assert!(assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_two,
-            AttackType::Regular,
-            2
-        ));
-        combat_damage_step_end(&mut game, AttackType::Regular);
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 4));
+        put_on_battlefield(&mut game, blocker_id);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        cleanup_step(&mut game);
 
-        let card = game.get_card(blocker_one).unwrap();
+        // -1/-1 counters stay on the creature after the damage wears off
+        let card = game.get_card(blocker_id).unwrap();
         assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 4);
-
-        let card = game.get_card(blocker_two).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        assert_eq!(card.state.get_counter(Counter::Minus), 2);
+        assert_eq!(card.state.power.current, -1);
+        assert_eq!(card.state.toughness.current, 2);
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 20);
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.state.toughness.current, 3);
     }
 
     #[test]
-    fn test_double_strike_multiple_blockers_auto_assign() {
+    fn test_indestructible() {
         let (mut game, player_id, opponent_id) = Game::new();
 
         let mut card = Card::new_creature(player_id, 2, 2);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Indestructible);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_one = game.add_card(Card::new_creature(opponent_id, 1, 2));
-        put_on_battlefield(&mut game, blocker_one);
-
-        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
-        put_on_battlefield(&mut game, blocker_two);
+        let mut card = Card::new_creature(opponent_id, 5, 5);
+        card.static_abilities.insert(StaticAbility::Deathtouch);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
 
-        fast_combat(&mut game, attacker_id, &[blocker_one, blocker_two]);
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
 
         let card = game.get_card(attacker_id).unwrap();
         assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 1);
-
-        let card = game.get_card(blocker_one).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
 
-        let card = game.get_card(blocker_two).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        cleanup_step(&mut game);
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 20);
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.state.toughness.current, 2);
     }
 
     #[test]
-    fn test_double_strike_blocked_by_first_strike_auto_assign() {
+    fn test_indestructible_infect() {
         let (mut game, player_id, opponent_id) = Game::new();
 
         let mut card = Card::new_creature(player_id, 2, 2);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        card.static_abilities.insert(StaticAbility::Indestructible);
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let mut card = Card::new_creature(opponent_id, 1, 2);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        let blocker_one = game.add_card(card);
-        put_on_battlefield(&mut game, blocker_one);
-
-        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
-        put_on_battlefield(&mut game, blocker_two);
+        let mut card = Card::new_creature(opponent_id, 2, 3);
+        card.static_abilities.insert(StaticAbility::Infect);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
 
-        fast_combat(&mut game, attacker_id, &[blocker_one, blocker_two]);
+        fast_combat(&mut game, attacker_id, &[blocker_id]);
 
+        // Indestructible creatures still die with zero toughness
         let card = game.get_card(attacker_id).unwrap();
         assert_eq!(card.zone, Zone::Graveyard);
+    }
 
-        let card = game.get_card(blocker_one).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+    #[test]
+    fn test_restricted_mana() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
 
-        let card = game.get_card(blocker_two).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let player = game.get_player(player_id).unwrap();
+        player.mana.add_entry(ManaEntry {
+            restriction: Some(ManaRestriction::Spell(CardType::Creature)),
+            ..ManaEntry::new(Mana::from("R"))
+        });
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 20);
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        action.choices.target = Choice::Player(opponent_id);
+        assert!(!action.valid(&mut game));
+
+        add_mana(&mut game, player_id, Mana::from("R"));
+        assert!(action.valid(&mut game));
+
+        play_card(&mut game, shock, action);
+        let player = game.get_player(player_id).unwrap();
+        assert_eq!(player.mana.total(), Mana::from("R"));
+        assert_eq!(
+            player
+                .mana
+                .available(&ManaUsage::Spell(IndexSet::from([CardType::Instant]))),
+            Mana::new()
+        );
     }
 
     #[test]
-    fn test_double_strike_blocked_by_first_strike() {
+    fn test_spent_mana_source() {
         let (mut game, player_id, opponent_id) = Game::new();
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
 
-        let mut card = Card::new_creature(player_id, 2, 2);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_land(player_id);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::Tap(Target::Source),
+            effect: Effect::Mana(Mana::from("R")),
+            target: Target::None,
+            restrictions: vec![],
+        });
+        let land_id = game.add_card(card);
+        put_on_battlefield(&mut game, land_id);
 
-        let mut card = Card::new_creature(opponent_id, 1, 2);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        let blocker_one = game.add_card(card);
-        put_on_battlefield(&mut game, blocker_one);
+        let mut action = create_ability_action(&mut game, player_id, land_id, 0).unwrap();
+        action.choices.cost = Choice::Card(land_id);
+        play_ability(&mut game, land_id, 0, action);
 
-        let blocker_two = game.add_card(Card::new_creature(opponent_id, 1, 2));
-        put_on_battlefield(&mut game, blocker_two);
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        action.choices.target = Choice::Player(opponent_id);
+        play_card(&mut game, shock, action);
 
-        fast_declare_attacker(&mut game, attacker_id);
-        fast_declare_blockers(&mut game, &[blocker_one, blocker_two], attacker_id);
-        combat_damage_step_start(&mut game);
-        reset_combat_assignments(&mut game, attacker_id);
+        let resolve = game.stack.last().unwrap();
+        assert_eq!(resolve.action.spent_mana.len(), 1);
+        assert_eq!(resolve.action.spent_mana[0].source_id, Some(land_id));
+    }
 
-        assert!(assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_two,
-            AttackType::FirstStrike,
-            2,
-        ));
-        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+    #[test]
+    fn test_phyrexian_mana_paid_with_life() {
+        let (mut game, player_id, opponent_id) = Game::new();
 
-        assert!(assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_one,
-            AttackType::Regular,
-            2
-        ));
-        combat_damage_step_end(&mut game, AttackType::Regular);
+        let mut card = Card::new_instant(player_id);
+        card.cost = Cost::Mana("{R/P}");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(1)),
+            target: Target::Player,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 1);
+        precombat_step(&mut game);
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::new());
+        action.choices.target = Choice::Player(opponent_id);
 
-        let card = game.get_card(blocker_one).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        // The player must have enough life for the Phyrexian symbol
+        game.get_player(player_id).unwrap().life = 1;
+        assert!(!action.valid(&mut game));
 
-        let card = game.get_card(blocker_two).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        // Paying the remaining life loses the game
+        game.get_player(player_id).unwrap().life = 2;
+        play_card(&mut game, card_id, action);
+        assert_eq!(game.get_card(card_id).unwrap().zone, Zone::Stack);
+        assert_eq!(game.get_player(player_id).unwrap().life, 0);
+        assert_eq!(game.status, GameStatus::Lose(player_id));
     }
 
     #[test]
-    fn test_trample() {
+    fn test_hexproof() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 4, 3);
-        card.static_abilities.insert(StaticAbility::Trample);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Hexproof);
+        let opponent_creature = game.add_card(card);
+        put_on_battlefield(&mut game, opponent_creature);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
-        put_on_battlefield(&mut game, blocker_id);
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Hexproof);
+        let player_creature = game.add_card(card);
+        put_on_battlefield(&mut game, player_creature);
 
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("R"));
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 2);
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        action.choices.target = Choice::Card(opponent_creature);
+        assert!(!action.valid(&mut game));
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 17);
+        action.choices.target = Choice::Card(player_creature);
+        assert!(action.valid(&mut game));
     }
 
     #[test]
-    fn test_trample_blocked() {
+    fn test_shroud() {
+        let (mut game, player_id, _) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Shroud);
+        let creature_id = game.add_card(card);
+        put_on_battlefield(&mut game, creature_id);
+
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
+
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        action.choices.target = Choice::Card(creature_id);
+        assert!(!action.valid(&mut game));
+    }
+
+    #[test]
+    fn test_ward() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 3, 3);
-        card.static_abilities.insert(StaticAbility::Trample);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.static_abilities
+            .insert(StaticAbility::Ward(&Cost::Mana("2")));
+        let creature_id = game.add_card(card);
+        put_on_battlefield(&mut game, creature_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 3));
-        put_on_battlefield(&mut game, blocker_id);
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("RRGG"));
 
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.target = Choice::Card(creature_id);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 2);
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        assert!(!action.valid(&mut game));
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        action.choices.cost = Choice::Mana(Mana::from("RGG"));
+        assert!(action.valid(&mut game));
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 20);
+        play_card(&mut game, shock, action);
+        resolve_auto(&mut game);
+
+        let creature = game.get_card(creature_id).unwrap();
+        assert_eq!(creature.zone, Zone::Graveyard);
+
+        let player = game.get_player(player_id).unwrap();
+        assert_eq!(player.mana.total(), Mana::from("R"));
     }
 
     #[test]
-    fn test_trample_first_strike() {
+    fn test_protection_from_color_targeting() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 3, 3);
-        card.static_abilities.insert(StaticAbility::Trample);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
-
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
-        put_on_battlefield(&mut game, blocker_id);
-
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
-
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 3);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.static_abilities
+            .insert(StaticAbility::Protection(Quality::Color(Color::Red)));
+        let creature_id = game.add_card(card);
+        put_on_battlefield(&mut game, creature_id);
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        let shock = create_shock(&mut game, player_id);
+        precombat_step(&mut game);
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 18);
+        let mut action = create_card_action(&mut game, shock, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        action.choices.target = Choice::Card(creature_id);
+        assert!(!action.valid(&mut game));
     }
 
     #[test]
-    fn test_trample_double_strike_auto_assign() {
+    fn test_protection_from_color_blocking() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 3, 3);
-        card.static_abilities.insert(StaticAbility::Trample);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities
+            .insert(StaticAbility::Protection(Quality::Color(Color::Green)));
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
-        put_on_battlefield(&mut game, blocker_id);
-
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.cost = Cost::Mana("1G");
+        let green_blocker = game.add_card(card);
+        put_on_battlefield(&mut game, green_blocker);
 
-        let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 3);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.cost = Cost::Mana("1W");
+        let white_blocker = game.add_card(card);
+        put_on_battlefield(&mut game, white_blocker);
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+        declare_attackers_step_start(&mut game);
+        declare_attacker(&mut game, attacker_id, opponent_id);
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 15);
+        assert!(!can_declare_blocker(&mut game, green_blocker, attacker_id));
+        assert!(can_declare_blocker(&mut game, white_blocker, attacker_id));
     }
 
     #[test]
-    fn test_trample_double_strike() {
+    fn test_protection_from_type_prevents_damage() {
         let (mut game, player_id, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 3, 3);
-        card.static_abilities.insert(StaticAbility::Trample);
-        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let mut card = Card::new_creature(player_id, 2, 2);
+        card.static_abilities
+            .insert(StaticAbility::Protection(Quality::Type(CardType::Creature)));
         card.static_abilities.insert(StaticAbility::Haste);
         let attacker_id = game.add_card(card);
         put_on_battlefield(&mut game, attacker_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 3, 2));
         put_on_battlefield(&mut game, blocker_id);
 
-        fast_declare_attacker(&mut game, attacker_id);
-        fast_declare_blockers(&mut game, &[blocker_id], attacker_id);
-        combat_damage_step_start(&mut game);
-        reset_combat_assignments(&mut game, attacker_id);
-
-        assert!(assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_id,
-            AttackType::FirstStrike,
-            1,
-        ));
-        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        let mut card = Card::new_artifact(player_id);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::None,
+            effect: Effect::Damage(Amount::Fixed(1)),
+            target: Target::Creature,
+            restrictions: vec![],
+        });
+        let artifact_id = game.add_card(card);
+        put_on_battlefield(&mut game, artifact_id);
 
-        assert!(!assign_combat_damage(
-            &mut game,
-            attacker_id,
-            blocker_id,
-            AttackType::Regular,
-            1
-        ));
-        combat_damage_step_end(&mut game, AttackType::Regular);
+        // Damage from creatures is prevented, damage from other sources is not
+        deal_damage(&mut game, blocker_id, attacker_id, 3);
+        let card = game.get_card(attacker_id).unwrap();
+        assert_eq!(card.state.toughness.current, 2);
 
+        deal_damage(&mut game, artifact_id, attacker_id, 1);
         let card = game.get_card(attacker_id).unwrap();
-        assert_eq!(card.zone, Zone::Battlefield);
-        assert_eq!(card.state.toughness.current, 3);
+        assert_eq!(card.state.toughness.current, 1);
+    }
 
-        let card = game.get_card(blocker_id).unwrap();
-        assert_eq!(card.zone, Zone::Graveyard);
+    #[test]
+    fn test_flash() {
+        let (mut game, _, opponent_id) = Game::new();
 
-        let opponent = game.get_player(opponent_id).unwrap();
-        assert_eq!(opponent.life, 15);
+        let mut card = Card::new_creature(opponent_id, 2, 2);
+        card.static_abilities.insert(StaticAbility::Flash);
+        let flash_id = game.add_card(card);
+        put_in_hand(&mut game, flash_id);
+
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_in_hand(&mut game, creature_id);
+
+        upkeep_step(&mut game);
+        pass_priority(&mut game);
+
+        assert!(can_play_card(&mut game, flash_id, opponent_id));
+        assert!(!can_play_card(&mut game, creature_id, opponent_id));
     }
 
     #[test]
-    fn test_deathtouch() {
-        let (mut game, player_id, opponent_id) = Game::new();
+    fn test_grant_flash() {
+        let (mut game, _, opponent_id) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 1, 1);
-        card.static_abilities.insert(StaticAbility::Deathtouch);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_enchantment(opponent_id);
+        card.static_abilities
+            .insert(StaticAbility::GrantFlash(CardType::Creature));
+        let enchantment_id = game.add_card(card);
+        put_on_battlefield(&mut game, enchantment_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 8, 8));
-        put_on_battlefield(&mut game, blocker_id);
+        let creature_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_in_hand(&mut game, creature_id);
 
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let sorcery_id = game.add_card(Card::new_sorcery(opponent_id));
+        put_in_hand(&mut game, sorcery_id);
 
-        let attacker = game.get_card(attacker_id).unwrap();
-        assert_eq!(attacker.zone, Zone::Graveyard);
+        upkeep_step(&mut game);
+        pass_priority(&mut game);
 
-        let blocker = game.get_card(blocker_id).unwrap();
-        assert_eq!(blocker.zone, Zone::Graveyard);
+        assert!(can_play_card(&mut game, creature_id, opponent_id));
+        assert!(!can_play_card(&mut game, sorcery_id, opponent_id));
     }
 
     #[test]
-    fn test_deathtouch_multiple_blockers() {
-        let (mut game, player_id, opponent_id) = Game::new();
+    fn test_cast_only_during_combat() {
+        let (mut game, player_id, _) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 2, 2);
-        card.static_abilities.insert(StaticAbility::Deathtouch);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_instant(player_id);
+        card.static_abilities
+            .insert(StaticAbility::CastOnlyDuringCombat);
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
 
-        let blocker_one = game.add_card(Card::new_creature(opponent_id, 4, 6));
-        put_on_battlefield(&mut game, blocker_one);
+        precombat_step(&mut game);
+        assert!(!can_play_card(&mut game, card_id, player_id));
 
-        let blocker_two = game.add_card(Card::new_creature(opponent_id, 3, 8));
-        put_on_battlefield(&mut game, blocker_two);
+        combat_begin_step(&mut game);
+        assert!(can_play_card(&mut game, card_id, player_id));
+    }
 
-        fast_declare_attacker(&mut game, attacker_id);
-        fast_declare_blockers(&mut game, &[blocker_one, blocker_two], attacker_id);
-        combat_damage_step_start(&mut game);
-        reset_combat_assignments(&mut game, attacker_id);
-        assign_combat_damage(&mut game, attacker_id, blocker_one, AttackType::Regular, 1);
-        assign_combat_damage(&mut game, attacker_id, blocker_two, AttackType::Regular, 1);
-        combat_damage_step_end(&mut game, AttackType::Regular);
+    #[test]
+    fn test_cast_only_before_attackers() {
+        let (mut game, player_id, _) = Game::new();
 
-        let attacker = game.get_card(attacker_id).unwrap();
-        assert_eq!(attacker.zone, Zone::Graveyard);
+        let mut card = Card::new_instant(player_id);
+        card.static_abilities
+            .insert(StaticAbility::CastOnlyBeforeAttackers);
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
 
-        let blocker = game.get_card(blocker_one).unwrap();
-        assert_eq!(blocker.zone, Zone::Graveyard);
+        combat_begin_step(&mut game);
+        assert!(can_play_card(&mut game, card_id, player_id));
 
-        let blocker = game.get_card(blocker_one).unwrap();
-        assert_eq!(blocker.zone, Zone::Graveyard);
+        declare_attackers_step_start(&mut game);
+        declare_attackers_step_end(&mut game);
+        assert!(!can_play_card(&mut game, card_id, player_id));
     }
 
     #[test]
-    fn test_deathtouch_first_strike() {
-        let (mut game, player_id, opponent_id) = Game::new();
+    fn test_split_second() {
+        let (mut game, player_id, _) = Game::new();
 
-        let mut card = Card::new_creature(player_id, 1, 1);
-        card.static_abilities.insert(StaticAbility::Deathtouch);
-        card.static_abilities.insert(StaticAbility::FirstStrike);
-        card.static_abilities.insert(StaticAbility::Haste);
-        let attacker_id = game.add_card(card);
-        put_on_battlefield(&mut game, attacker_id);
+        let mut card = Card::new_instant(player_id);
+        card.cost = Cost::Mana("R");
+        card.static_abilities.insert(StaticAbility::SplitSecond);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Damage(Amount::Fixed(3)),
+            target: Target::Player,
+        });
+        let split_second_id = game.add_card(card);
+        put_in_hand(&mut game, split_second_id);
 
-        let blocker_id = game.add_card(Card::new_creature(opponent_id, 8, 8));
-        put_on_battlefield(&mut game, blocker_id);
+        let shock = create_shock(&mut game, player_id);
 
-        fast_combat(&mut game, attacker_id, &[blocker_id]);
+        let mut card = Card::new_land(player_id);
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::Tap(Target::Source),
+            effect: Effect::Mana(Mana::from("R")),
+            target: Target::None,
+            restrictions: vec![],
+        });
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::None,
+            effect: Effect::Damage(Amount::Fixed(1)),
+            target: Target::Player,
+            restrictions: vec![],
+        });
+        let land_id = game.add_card(card);
+        put_on_battlefield(&mut game, land_id);
 
-        let attacker = game.get_card(attacker_id).unwrap();
-        assert_eq!(attacker.zone, Zone::Battlefield);
+        precombat_step(&mut game);
+        add_mana(&mut game, player_id, Mana::from("R"));
 
-        let blocker = game.get_card(blocker_id).unwrap();
-        assert_eq!(blocker.zone, Zone::Graveyard);
+        let mut action = create_card_action(&mut game, split_second_id, player_id).unwrap();
+        action.choices.cost = Choice::Mana(Mana::from("R"));
+        action.choices.target = Choice::Player(player_id);
+        play_card(&mut game, split_second_id, action);
+
+        assert!(!can_play_card(&mut game, shock, player_id));
+
+        assert!(!can_activate_ability(&mut game, land_id, 1, player_id));
+        assert!(create_ability_action(&mut game, player_id, land_id, 1).is_none());
+        let action =
+            Action::new(player_id, land_id).with_target(Target::Player, Choice::Player(player_id));
+        assert!(!play_ability(&mut game, land_id, 1, action));
+
+        // Mana abilities can still be activated
+        let mut action = create_ability_action(&mut game, player_id, land_id, 0).unwrap();
+        action.choices.cost = Choice::Card(land_id);
+        assert!(play_ability(&mut game, land_id, 0, action));
     }
 }
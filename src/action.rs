@@ -1,8 +1,11 @@
 use crate::{
-    abilities::{Cost, Target},
-    card::{put_on_graveyard, tap_card, CardType, Zone},
+    abilities::{
+        can_be_targeted, get_cost_modifier, get_ward_costs, lose_life, Amount, CastCosts, Cost,
+        Filter, StaticAbility, Target,
+    },
+    card::{put_in_exile, put_in_hand, put_on_graveyard, tap_card, CardType, Counter, Zone},
     game::{Game, ObjectId},
-    mana::Mana,
+    mana::{Mana, ManaCost, ManaEntry, ManaSymbol, ManaUsage, ParseManaError},
 };
 
 #[derive(Clone, Debug)]
@@ -11,6 +14,15 @@ pub struct Action {
     pub card_id: ObjectId,
     pub(crate) required: Required,
     pub choices: Choices,
+
+    /// Defines what the mana paid for this action is spent on
+    pub mana_usage: ManaUsage,
+
+    /// Mana spent from the mana pool to pay this action
+    pub spent_mana: Vec<ManaEntry>,
+
+    /// Optional costs chosen for casting the spell
+    pub cast_costs: CastCosts,
 }
 
 impl Action {
@@ -21,109 +33,530 @@ impl Action {
             required: Required {
                 cost: Cost::None,
                 target: Target::None,
+                mode_count: 0,
+                modes: vec![],
+                division: None,
             },
             choices: Choices {
                 cost: Choice::None,
                 target: Choice::None,
                 effect: Choice::None,
+                modes: vec![],
+                x: 0,
+                division: vec![],
             },
+            mana_usage: ManaUsage::Ability,
+            spent_mana: vec![],
+            cast_costs: CastCosts::default(),
         }
     }
 
+    pub fn set_cast_costs(&mut self, costs: CastCosts) {
+        self.cast_costs = costs;
+    }
+
+    pub fn set_mana_usage(&mut self, usage: ManaUsage) {
+        self.mana_usage = usage;
+    }
+
     pub fn set_required_cost(&mut self, cost: Cost) {
         self.required.cost = cost;
     }
 
     pub fn set_required_target(&mut self, target: Target) {
-        match target {
-            Target::Owner => {
-                self.choices.target = Choice::Player(self.player_id);
-            }
-            _ => {}
+        if target == Target::Owner {
+            self.choices.target = Choice::Player(self.player_id);
         }
         self.required.target = target;
     }
 
-    pub fn pay(&self, game: &mut Game) -> bool {
-        self.pay_cost(game, &self.required.cost, &self.choices.cost)
+    /// Sets the number of modes to choose and the targets of all modes of a modal spell.
+    pub fn set_required_modes(&mut self, count: usize, targets: Vec<Target>) {
+        self.required.mode_count = count;
+        self.required.modes = targets;
     }
 
-    fn pay_cost(&self, game: &mut Game, cost: &Cost, choice: &Choice) -> bool {
-        match &cost {
-            Cost::None => true,
-            Cost::Mana(_) => match &choice {
-                Choice::Mana(mana) => {
-                    if let Some(player) = game.get_player(self.player_id) {
-                        player.mana -= *mana;
-                        true
-                    } else {
-                        false
-                    }
+    /// Sets the total amount of damage divided among the targets.
+    pub fn set_required_division(&mut self, amount: Amount) {
+        self.required.division = Some(amount);
+    }
+
+    /// Returns the action which resolves the mode chosen at the position,
+    /// targeting what was chosen for that mode.
+    pub fn get_mode_action(&self, position: usize) -> Action {
+        let choice = match &self.choices.target {
+            Choice::And(choices) => choices.get(position).cloned().unwrap_or_default(),
+            _ => Choice::None,
+        };
+        let target = self
+            .choices
+            .modes
+            .get(position)
+            .and_then(|index| self.required.modes.get(*index))
+            .cloned()
+            .unwrap_or(Target::None);
+        self.with_target(target, choice)
+    }
+
+    /// Returns this action with the single target chosen for the requirement.
+    pub fn with_target(&self, target: Target, choice: Choice) -> Action {
+        let mut action = self.clone();
+        action.choices.target = choice;
+        action.set_required_target(target);
+        action
+    }
+
+    /// Returns this action applied to a player of a group player target, the player
+    /// is both its target and the player the effect is resolved for.
+    pub fn for_player(&self, player_id: ObjectId) -> Action {
+        let mut action = self.with_target(Target::Player, Choice::Player(player_id));
+        action.player_id = player_id;
+        action
+    }
+
+    /// Determines whether the target chosen for the single target requirement is still legal.
+    pub fn has_legal_target(&self, game: &mut Game) -> bool {
+        self.valid_target(game, &self.required.target)
+    }
+
+    /// Returns how many of the chosen targets, including the targets of the chosen modes,
+    /// are still legal and how many targets were chosen in total.
+    pub fn count_legal_targets(&self, game: &mut Game) -> (usize, usize) {
+        let mut actions = vec![self.clone()];
+        actions
+            .extend((0..self.choices.modes.len()).map(|position| self.get_mode_action(position)));
+
+        let (mut legal, mut total) = (0, 0);
+        for action in actions {
+            for (target, choice) in action.get_targets() {
+                if !target.is_targeted() {
+                    continue;
                 }
-                Choice::And(choices) => choices
-                    .iter()
-                    .any(|choice| self.pay_cost(game, cost, choice)),
-                _ => false,
-            },
-            Cost::Tap(_) => match &choice {
-                Choice::Card(card_id) => tap_card(game, *card_id, Some(self.card_id)),
-                Choice::And(choices) => choices
-                    .iter()
-                    .any(|choice| self.pay_cost(game, cost, choice)),
-                _ => false,
+                total += 1;
+                if action.with_target(target, choice).has_legal_target(game) {
+                    legal += 1;
+                }
+            }
+        }
+        (legal, total)
+    }
+
+    /// Returns each chosen target with its requirement, in the order of the target slots.
+    pub fn get_targets(&self) -> Vec<(Target, Choice)> {
+        match (&self.required.target, &self.choices.target) {
+            (Target::Slots(slots), Choice::And(choices)) => slots
+                .iter()
+                .zip(choices.iter())
+                .flat_map(|(slot, choice)| {
+                    choice
+                        .get_targets()
+                        .into_iter()
+                        .map(|choice| (slot.target.clone(), choice))
+                })
+                .collect(),
+            (Target::Slots(_), _) => vec![],
+            (target, choice) => vec![(target.clone(), choice.clone())],
+        }
+    }
+
+    pub fn pay(&mut self, game: &mut Game) -> bool {
+        let costs = self.get_costs(game);
+        let assigned = if let Some(assigned) = self.assign_cost_cards(game, &costs) {
+            assigned
+        } else {
+            return false;
+        };
+
+        // Mana is paid once for the total mana cost of the action
+        let mana = if let Some(mana) = self.get_mana_to_pay(game) {
+            mana
+        } else {
+            return false;
+        };
+        if !mana.is_empty() {
+            let chosen = match self.choices.cost.get_mana(&mana) {
+                Some(chosen) => chosen,
+                None => return false,
+            };
+            let spent = match game.get_player(self.player_id) {
+                Some(player) => player.mana.spend(&chosen, &self.mana_usage),
+                None => None,
+            };
+            match spent {
+                Some(spent) => self.spent_mana = spent,
+                None => return false,
+            }
+
+            // Phyrexian symbols not covered by the chosen mana are paid with life
+            let life = chosen.pay_with_life(&mana).map_or(0, |(_, life)| life);
+            if life > 0 {
+                lose_life(game, self.player_id, life);
+            }
+        }
+
+        for (card_id, _) in self.choices.cost.get_substitutes() {
+            let paid = match game.get_card(card_id) {
+                Some(card) if card.zone == Zone::Graveyard => {
+                    put_in_exile(game, card_id);
+                    true
+                }
+                Some(_) => tap_card(game, card_id, Some(self.card_id)),
+                None => false,
+            };
+            if !paid {
+                return false;
+            }
+        }
+
+        // Costs of the source are paid before the source possibly leaves the battlefield
+        if !costs
+            .iter()
+            .filter(|cost| !cost.requires_card())
+            .all(|cost| self.pay_cost(game, cost))
+        {
+            return false;
+        }
+        assigned
+            .iter()
+            .all(|(cost, card_id)| self.pay_card_cost(game, cost, *card_id))
+    }
+
+    fn pay_cost(&self, game: &mut Game, cost: &Cost) -> bool {
+        match cost {
+            Cost::Life(life) => {
+                if game.get_player(self.player_id).is_none() {
+                    return false;
+                }
+                lose_life(game, self.player_id, *life);
+                true
+            }
+            Cost::RemoveCounters(counter, amount) => match game.get_card(self.card_id) {
+                Some(card) => card.state.remove_counters(*counter, *amount),
+                None => false,
             },
-            Cost::Sacrifice(_) => match &choice {
-                Choice::Card(card_id) => {
-                    put_on_graveyard(game, *card_id);
+            Cost::Loyalty(loyalty) => match game.get_card(self.card_id) {
+                Some(card) if *loyalty >= 0 => {
+                    card.state.add_counters(Counter::Loyalty, *loyalty as u16);
                     true
                 }
-                Choice::And(choices) => choices
-                    .iter()
-                    .any(|choice| self.pay_cost(game, cost, choice)),
-                _ => false,
+                Some(card) => card
+                    .state
+                    .remove_counters(Counter::Loyalty, loyalty.unsigned_abs()),
+                None => false,
             },
-            Cost::And(costs) => costs.iter().all(|cost| self.pay_cost(game, cost, choice)),
+            _ => true,
+        }
+    }
+
+    fn pay_card_cost(&self, game: &mut Game, cost: &Cost, card_id: ObjectId) -> bool {
+        match cost {
+            Cost::Tap(_) => tap_card(game, card_id, Some(self.card_id)),
+            Cost::Sacrifice(_) | Cost::Discard(_) => {
+                put_on_graveyard(game, card_id);
+                true
+            }
+            Cost::ExileFromGraveyard(_) => {
+                put_in_exile(game, card_id);
+                true
+            }
+            Cost::ReturnToHand(_) => {
+                put_in_hand(game, card_id);
+                true
+            }
+            _ => false,
         }
     }
 
     pub fn valid(&self, game: &mut Game) -> bool {
-        self.valid_cost(game, &self.required.cost) && self.valid_target(game, &self.required.target)
+        self.valid_costs(game)
+            && self.valid_target(game, &self.required.target)
+            && self.valid_modes(game)
+            && self.valid_division(game)
+    }
+
+    /// Checks that the divided amount is split among all targets with at least one
+    /// assigned to each of them.
+    fn valid_division(&self, game: &mut Game) -> bool {
+        let division = &self.choices.division;
+        let total = match &self.required.division {
+            Some(amount) => amount.compute(game, self),
+            None => return division.is_empty(),
+        };
+        division.len() == self.get_targets().len()
+            && division.iter().all(|amount| *amount > 0)
+            && division.iter().sum::<u16>() == total
+    }
+
+    /// Checks that the required number of different modes is chosen
+    /// and that each chosen mode has a valid target.
+    fn valid_modes(&self, game: &mut Game) -> bool {
+        let chosen = &self.choices.modes;
+        if self.required.modes.is_empty() {
+            return chosen.is_empty();
+        }
+        if chosen.len() != self.required.mode_count {
+            return false;
+        }
+        chosen.iter().enumerate().all(|(position, index)| {
+            *index < self.required.modes.len()
+                && !chosen[..position].contains(index)
+                && self
+                    .get_mode_action(position)
+                    .valid_target(game, &self.required.modes[*index])
+        })
+    }
+
+    fn valid_costs(&self, game: &mut Game) -> bool {
+        let mana = if let Some(mana) = self.get_mana_to_pay(game) {
+            mana
+        } else {
+            return false;
+        };
+        if !mana.is_empty() && !self.valid_mana(game, &mana) {
+            return false;
+        }
+
+        let costs = self.get_costs(game);
+        if !costs.iter().all(|cost| self.valid_cost(game, cost)) {
+            return false;
+        }
+
+        // The player cannot pay more life than they have
+        let life = self.get_life_to_pay(&mana, &costs);
+        match game.get_player(self.player_id) {
+            Some(player) if player.life >= life as i16 => {}
+            _ => return false,
+        }
+
+        match self.assign_cost_cards(game, &costs) {
+            // Objects paying for mana symbols cannot pay other costs
+            Some(assigned) => self
+                .choices
+                .cost
+                .get_substitutes()
+                .iter()
+                .all(|(card_id, _)| assigned.iter().all(|(_, assigned)| assigned != card_id)),
+            None => false,
+        }
+    }
+
+    /// Returns the mana cost remaining after the chosen objects pay for their mana symbols.
+    /// Returns None if the cost is malformed or a chosen object cannot pay for its symbol.
+    pub(crate) fn get_mana_to_pay(&self, game: &mut Game) -> Option<ManaCost> {
+        let mut mana = self.get_mana_cost(game).ok()?;
+        let substitutes = self.choices.cost.get_substitutes();
+
+        for (index, (card_id, symbol)) in substitutes.iter().enumerate() {
+            let repeated = substitutes[..index].iter().any(|(used, _)| used == card_id);
+            if repeated || !self.valid_substitute(game, *card_id, symbol) {
+                return None;
+            }
+            mana = mana.without_symbol(symbol)?;
+        }
+        Some(mana)
+    }
+
+    /// Determines whether the object can pay for the mana symbol with convoke, delve
+    /// or improvise of the spell.
+    fn valid_substitute(&self, game: &mut Game, card_id: ObjectId, symbol: &ManaSymbol) -> bool {
+        if !matches!(self.mana_usage, ManaUsage::Spell(_)) {
+            return false;
+        }
+        let abilities = match game.get_card(self.card_id) {
+            Some(spell) => spell.static_abilities.clone(),
+            None => return false,
+        };
+        let card = match game.get_card(card_id) {
+            Some(card) if card.owner_id == self.player_id => card,
+            _ => return false,
+        };
+
+        let generic = *symbol == ManaSymbol::Generic(1);
+        let untapped = card.zone == Zone::Battlefield && !card.state.tapped.current;
+        let convoke = abilities.contains(&StaticAbility::Convoke)
+            && untapped
+            && card.is_creature()
+            && (generic
+                || symbol.cmc() == 1 && symbol.colors().iter().any(|color| card.has_color(color)));
+        let delve =
+            abilities.contains(&StaticAbility::Delve) && card.zone == Zone::Graveyard && generic;
+        let improvise = abilities.contains(&StaticAbility::Improvise)
+            && untapped
+            && card.is_type(&CardType::Artifact)
+            && generic;
+
+        convoke || delve || improvise
+    }
+
+    /// Returns all costs of this action, including the additional costs
+    /// and the ward costs of its targets.
+    fn get_costs(&self, game: &mut Game) -> Vec<Cost> {
+        let mut costs = self.required.cost.flatten();
+        for cost in self.cast_costs.additional.iter() {
+            costs.extend(cost.cost().flatten());
+        }
+        for cost in self.get_ward_costs(game) {
+            costs.extend(cost.flatten());
+        }
+        costs
+    }
+
+    /// Returns the total amount of mana required for this action, including
+    /// the additional costs, the ward costs of its targets and the cost modifiers.
+    pub fn get_mana_cost(&self, game: &mut Game) -> Result<ManaCost, ParseManaError> {
+        let mut total = self.required.cost.get_mana_cost()?.with_x(self.choices.x);
+        for cost in self.cast_costs.additional.iter() {
+            total += cost.cost().get_mana_cost()?;
+        }
+        for cost in self.get_ward_costs(game) {
+            total += cost.get_mana_cost()?;
+        }
+
+        if let ManaUsage::Spell(types) = &self.mana_usage {
+            let modifier = get_cost_modifier(game, self.player_id, types);
+            total = total.with_generic_change(modifier);
+        }
+        Ok(total)
+    }
+
+    /// Determines whether the chosen mana pays the cost and can be spent
+    /// from the mana pool on this action.
+    fn valid_mana(&self, game: &mut Game, cost: &ManaCost) -> bool {
+        let chosen = if let Some(chosen) = self.choices.cost.get_mana(cost) {
+            chosen
+        } else {
+            return false;
+        };
+
+        match game.get_player(self.player_id) {
+            Some(player) => player.mana.available(&self.mana_usage).contains(&chosen),
+            None => false,
+        }
+    }
+
+    /// Returns the life paid for the life costs and the Phyrexian symbols
+    /// not paid with the chosen mana.
+    fn get_life_to_pay(&self, mana: &ManaCost, costs: &[Cost]) -> u16 {
+        let phyrexian = self
+            .choices
+            .cost
+            .get_mana(mana)
+            .and_then(|chosen| chosen.pay_with_life(mana))
+            .map_or(0, |(_, life)| life);
+        let life: u16 = costs
+            .iter()
+            .map(|cost| match cost {
+                Cost::Life(life) => *life,
+                _ => 0,
+            })
+            .sum();
+        phyrexian + life
+    }
+
+    fn get_ward_costs(&self, game: &mut Game) -> Vec<Cost> {
+        self.choices
+            .target
+            .get_cards()
+            .into_iter()
+            .flat_map(|card_id| get_ward_costs(game, card_id, self.player_id))
+            .collect()
     }
 
+    /// Validates the costs which are not paid with a chosen card.
     fn valid_cost(&self, game: &mut Game, cost: &Cost) -> bool {
         match cost {
-            Cost::None => true,
-            Cost::Mana(mana) => self.choices.cost.validate_mana(&Mana::from(*mana)),
-            Cost::Tap(target) => match target {
-                Target::Source => {
-                    if !self.choices.cost.validate_card(self.card_id) {
-                        return false;
-                    }
+            Cost::RemoveCounters(counter, amount) => match game.get_card(self.card_id) {
+                Some(card) => card.state.get_counter(*counter) >= *amount,
+                None => false,
+            },
+            Cost::Loyalty(loyalty) => match game.get_card(self.card_id) {
+                Some(card) => card.state.get_counter(Counter::Loyalty) as i16 + *loyalty >= 0,
+                None => false,
+            },
+            _ => true,
+        }
+    }
 
-                    if let Some(card) = game.get_card(self.card_id) {
-                        // Creatures can be tapped for their ability only if they don't have summoning sickness
-                        return card.kind != CardType::Creature
-                            || !card.state.summoning_sickness.current;
-                    }
+    /// Assigns the chosen cards to the costs which require a card.
+    /// Every card can pay only one of the costs.
+    fn assign_cost_cards(&self, game: &mut Game, costs: &[Cost]) -> Option<Vec<(Cost, ObjectId)>> {
+        let costs: Vec<Cost> = costs
+            .iter()
+            .filter(|cost| cost.requires_card())
+            .cloned()
+            .collect();
+        let cards = self.choices.cost.get_cards();
 
-                    true
-                }
-                _ => true,
-            },
-            Cost::Sacrifice(target) => match target {
-                Target::Source => self.choices.cost.validate_card(self.card_id),
-                Target::Creature => {
-                    if let Some(creature_id) = self.choices.cost.validate_creature(game) {
-                        if let Some(card) = game.get_card(creature_id) {
-                            return card.zone == Zone::Battlefield
-                                && card.owner_id == self.player_id;
-                        }
-                    }
-                    false
-                }
-                _ => false,
+        let assigned = self.assign_cards(game, &costs, &cards, &mut vec![])?;
+        Some(costs.into_iter().zip(assigned).collect())
+    }
+
+    fn assign_cards(
+        &self,
+        game: &mut Game,
+        costs: &[Cost],
+        cards: &[ObjectId],
+        used: &mut Vec<ObjectId>,
+    ) -> Option<Vec<ObjectId>> {
+        let (cost, rest) = if let Some(split) = costs.split_first() {
+            split
+        } else {
+            return Some(vec![]);
+        };
+
+        for card_id in cards.iter() {
+            let reused = used.contains(card_id) && !cost.requires_source();
+            if reused || !self.valid_cost_card(game, cost, *card_id) {
+                continue;
+            }
+
+            used.push(*card_id);
+            if let Some(mut assigned) = self.assign_cards(game, rest, cards, used) {
+                assigned.insert(0, *card_id);
+                return Some(assigned);
+            }
+            used.pop();
+        }
+        None
+    }
+
+    /// Determines whether the card can be used to pay the cost.
+    fn valid_cost_card(&self, game: &mut Game, cost: &Cost, card_id: ObjectId) -> bool {
+        let player_id = self.player_id;
+        let is_source = card_id == self.card_id;
+        let matches = match cost {
+            Cost::Tap(target)
+            | Cost::Sacrifice(target)
+            | Cost::Discard(target)
+            | Cost::ExileFromGraveyard(target)
+            | Cost::ReturnToHand(target) => match target {
+                Target::Source => is_source,
+                Target::Card => true,
+                target => target
+                    .get_filter()
+                    .is_some_and(|filter| filter.matches(game, card_id, self.card_id, player_id)),
             },
-            Cost::And(costs) => costs.iter().all(|cost| self.valid_cost(game, cost)),
+            _ => false,
+        };
+
+        let card = if let Some(card) = game.get_card(card_id) {
+            card
+        } else {
+            return false;
+        };
+        let controlled = |zone: Zone| card.zone == zone && card.owner_id == player_id;
+
+        match cost {
+            Cost::Tap(_) => {
+                // Creatures can be tapped for their ability only if they don't have summoning sickness
+                let can_tap =
+                    !is_source || !card.is_creature() || !card.state.summoning_sickness.current;
+                matches && controlled(Zone::Battlefield) && !card.state.tapped.current && can_tap
+            }
+            Cost::Sacrifice(_) | Cost::ReturnToHand(_) => matches && controlled(Zone::Battlefield),
+            Cost::Discard(_) => matches && !is_source && controlled(Zone::Hand),
+            Cost::ExileFromGraveyard(_) => matches && controlled(Zone::Graveyard),
+            _ => false,
         }
     }
 
@@ -131,10 +564,62 @@ impl Action {
         match &target {
             Target::None => true,
             Target::Source => self.choices.target.validate_card(self.card_id),
-            Target::Player => self.choices.target.validate_player(None),
-            Target::Creature => self.choices.target.validate_creature(game).is_some(),
             Target::Owner => self.choices.target.validate_player(Some(self.player_id)),
+            // Group player targets apply to each player without choosing targets
+            Target::EachPlayer | Target::EachOpponent => true,
+            Target::Card => match self.choices.target {
+                Choice::Card(_) => self.valid_filtered_target(game, &Filter::Any),
+                _ => false,
+            },
+            Target::Player
+            | Target::Opponent
+            | Target::Creature
+            | Target::Planeswalker
+            | Target::Any
+            | Target::Filtered(_) => match target.get_filter() {
+                Some(filter) => self.valid_filtered_target(game, &filter),
+                None => false,
+            },
             Target::AnyOf(options) => options.iter().any(|option| self.valid_target(game, option)),
+            Target::Slots(slots) => {
+                let choices = match &self.choices.target {
+                    Choice::And(choices) if choices.len() == slots.len() => choices,
+                    _ => return false,
+                };
+                for (slot, choice) in slots.iter().zip(choices.iter()) {
+                    let targets = choice.get_targets();
+                    if targets.len() < slot.min || targets.len() > slot.max {
+                        return false;
+                    }
+                    for (index, target) in targets.iter().enumerate() {
+                        // Each object can be chosen only once for the same slot
+                        if targets[..index].contains(target)
+                            || !self
+                                .with_target(slot.target.clone(), target.clone())
+                                .valid_target(game, &slot.target)
+                        {
+                            return false;
+                        }
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Determines whether the chosen card or player matches the filter of the target.
+    fn valid_filtered_target(&self, game: &mut Game, filter: &Filter) -> bool {
+        match self.choices.target {
+            Choice::Player(player_id) => {
+                game.get_player(player_id).is_some()
+                    && filter.matches_target(game, player_id, self.card_id, self.player_id)
+            }
+            Choice::Card(card_id) => {
+                game.get_card(card_id).is_some()
+                    && filter.matches_target(game, card_id, self.card_id, self.player_id)
+                    && can_be_targeted(game, card_id, self.card_id, self.player_id)
+            }
+            _ => false,
         }
     }
 }
@@ -143,6 +628,15 @@ impl Action {
 pub struct Required {
     pub cost: Cost,
     pub target: Target,
+
+    /// Number of different modes chosen for a modal spell
+    pub mode_count: usize,
+
+    /// Targets of all modes of a modal spell
+    pub modes: Vec<Target>,
+
+    /// Total amount divided among the targets
+    pub division: Option<Amount>,
 }
 
 #[derive(Clone, Debug)]
@@ -150,6 +644,16 @@ pub struct Choices {
     pub cost: Choice,
     pub target: Choice,
     pub effect: Choice,
+
+    /// Indexes of the modes chosen for a modal spell, whose targets
+    /// are chosen with `Choice::And` in the same order
+    pub modes: Vec<usize>,
+
+    /// Value chosen for X in the mana cost
+    pub x: u8,
+
+    /// Amounts assigned to each target in the order of the targets
+    pub division: Vec<u16>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
@@ -159,11 +663,24 @@ pub enum Choice {
     Mana(Mana),
     Player(ObjectId),
     Card(ObjectId),
+
+    /// Object which pays for the mana symbol with convoke, delve or improvise
+    Substitute(ObjectId, ManaSymbol),
+
+    /// Cards looked at from the top of a library split into the cards put back on top,
+    /// where the first card ends on top, the cards put on the bottom in the listed order
+    /// and the cards put into the graveyard
+    Arrange {
+        top: Vec<ObjectId>,
+        bottom: Vec<ObjectId>,
+        graveyard: Vec<ObjectId>,
+    },
+
     And(Vec<Choice>),
 }
 
 impl Choice {
-    pub fn validate_mana(&self, cost: &Mana) -> bool {
+    pub fn validate_mana(&self, cost: &ManaCost) -> bool {
         match self {
             Choice::Mana(mana) => mana.enough(cost),
             Choice::And(choices) => choices.iter().any(|choice| choice.validate_mana(cost)),
@@ -171,6 +688,49 @@ impl Choice {
         }
     }
 
+    /// Returns the first chosen mana which is enough to pay the specified cost.
+    pub fn get_mana(&self, cost: &ManaCost) -> Option<Mana> {
+        match self {
+            Choice::Mana(mana) if mana.enough(cost) => Some(*mana),
+            Choice::And(choices) => choices.iter().find_map(|choice| choice.get_mana(cost)),
+            _ => None,
+        }
+    }
+
+    /// Returns all objects paying for mana symbols in this choice.
+    pub fn get_substitutes(&self) -> Vec<(ObjectId, ManaSymbol)> {
+        match self {
+            Choice::Substitute(card_id, symbol) => vec![(*card_id, *symbol)],
+            Choice::And(choices) => choices
+                .iter()
+                .flat_map(|choice| choice.get_substitutes())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns all cards in this choice.
+    pub fn get_cards(&self) -> Vec<ObjectId> {
+        match self {
+            Choice::Card(card_id) => vec![*card_id],
+            Choice::And(choices) => choices
+                .iter()
+                .flat_map(|choice| choice.get_cards())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the objects chosen for a target slot, which can be a single choice,
+    /// several choices combined with `And`, or no choice.
+    pub fn get_targets(&self) -> Vec<Choice> {
+        match self {
+            Choice::None => vec![],
+            Choice::And(choices) => choices.clone(),
+            choice => vec![choice.clone()],
+        }
+    }
+
     pub fn validate_card(&self, card_id: ObjectId) -> bool {
         match self {
             Choice::Card(chosen_card) => *chosen_card == card_id,
@@ -181,34 +741,13 @@ impl Choice {
 
     pub fn validate_player(&self, player_id: Option<ObjectId>) -> bool {
         match self {
-            Choice::Player(chosen_player) => player_id == None || player_id == Some(*chosen_player),
+            Choice::Player(chosen_player) => {
+                player_id.is_none() || player_id == Some(*chosen_player)
+            }
             Choice::And(choices) => choices
                 .iter()
                 .any(|choice| choice.validate_player(player_id)),
             _ => false,
         }
     }
-
-    pub fn validate_creature(&self, game: &mut Game) -> Option<ObjectId> {
-        match self {
-            Choice::Card(card_id) => {
-                if let Some(card) = game.get_card(*card_id) {
-                    if card.kind == CardType::Creature {
-                        return Some(*card_id);
-                    }
-                }
-                None
-            }
-            Choice::And(choices) => {
-                for choice in choices.iter() {
-                    let card_id = choice.validate_creature(game);
-                    if card_id.is_some() {
-                        return card_id;
-                    }
-                }
-                None
-            }
-            _ => None,
-        }
-    }
 }
@@ -1,14 +1,15 @@
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
 use crate::{
     abilities::{
-        apply_static_abilities, ActivatedAbility, Cost, PlayAbility, StaticAbility,
-        TriggeredAbility,
+        apply_static_abilities, ActivatedAbility, AdditionalCost, AlternativeCost, Cost,
+        PlayAbility, Quality, StaticAbility, TriggeredAbility,
     },
-    events::{dispatch_event, CardEvent, Event},
+    events::{dispatch_event, CardEvent, Event, ZoneEvent},
     game::{Game, GameStatus, ObjectId, Value},
+    mana::{Color, Mana, COLORS},
 };
 
 #[derive(Default, Clone)]
@@ -16,37 +17,54 @@ pub struct Card {
     pub id: ObjectId,
     pub owner_id: ObjectId,
     pub name: String,
-    pub kind: CardType,
+    pub types: IndexSet<CardType>,
+    pub supertypes: IndexSet<CardSupertype>,
     pub subtypes: IndexSet<CardSubtype>,
     pub cost: Cost,
     pub zone: Zone,
 
+    /// Defines the colors of the card regardless of its mana cost
+    pub color_indicator: Option<IndexSet<Color>>,
+
     /// Defines the ability that happens when the card is resolved
     pub play_ability: Option<PlayAbility>,
 
+    /// Optional costs that can be paid in addition to the card cost
+    pub additional_costs: Vec<AdditionalCost>,
+
+    /// Costs that can be paid instead of the card cost
+    pub alternative_costs: Vec<AlternativeCost>,
+
     pub activated_abilities: Vec<ActivatedAbility>,
     pub triggered_abilities: Vec<TriggeredAbility>,
     pub static_abilities: IndexSet<StaticAbility>,
 
+    /// Number of loyalty counters the planeswalker enters the battlefield with
+    pub loyalty: u16,
+
+    /// Tokens are not cards, they cease to exist when they leave the battlefield
+    pub token: bool,
+
     pub state: CardState,
 }
 
 impl Card {
     pub fn new(owner_id: ObjectId) -> Card {
-        let mut card = Card::default();
-        card.owner_id = owner_id;
-        card
+        Card {
+            owner_id,
+            ..Default::default()
+        }
     }
 
     pub fn new_land(owner_id: ObjectId) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Land;
+        card.types.insert(CardType::Land);
         card
     }
 
     pub fn new_creature(owner_id: ObjectId, power: i16, toughness: i16) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Creature;
+        card.types.insert(CardType::Creature);
         card.state.power = Value::new(power);
         card.state.toughness = Value::new(toughness);
         card.state.summoning_sickness = Value::new(true);
@@ -55,28 +73,143 @@ impl Card {
 
     pub fn new_artifact(owner_id: ObjectId) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Artifact;
+        card.types.insert(CardType::Artifact);
         card
     }
 
     pub fn new_enchantment(owner_id: ObjectId) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Enchantment;
+        card.types.insert(CardType::Enchantment);
         card
     }
 
     pub fn new_instant(owner_id: ObjectId) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Instant;
+        card.types.insert(CardType::Instant);
         card
     }
 
     pub fn new_sorcery(owner_id: ObjectId) -> Card {
         let mut card = Card::new(owner_id);
-        card.kind = CardType::Sorcery;
+        card.types.insert(CardType::Sorcery);
+        card
+    }
+
+    pub fn new_planeswalker(owner_id: ObjectId, loyalty: u16) -> Card {
+        let mut card = Card::new(owner_id);
+        card.types.insert(CardType::Planeswalker);
+        card.loyalty = loyalty;
         card
     }
 
+    /// Returns the colors of this card.
+    ///
+    /// Colors are defined by the mana symbols in the card cost unless the card has
+    /// a color indicator or its colors were changed by an effect.
+    pub fn colors(&self) -> IndexSet<Color> {
+        if let Some(colors) = &self.state.colors {
+            colors.clone()
+        } else if let Some(colors) = &self.color_indicator {
+            colors.clone()
+        } else {
+            self.cost.get_mana_cost().unwrap_or_default().colors()
+        }
+    }
+
+    pub fn has_quality(&self, quality: &Quality) -> bool {
+        match quality {
+            Quality::Color(color) => self.has_color(color),
+            Quality::Type(kind) => self.is_type(kind),
+            Quality::Subtype(subtype) => self.subtypes.contains(subtype),
+        }
+    }
+
+    pub fn is_type(&self, kind: &CardType) -> bool {
+        self.types.contains(kind)
+    }
+
+    pub fn is_creature(&self) -> bool {
+        self.is_type(&CardType::Creature)
+    }
+
+    pub fn is_legendary(&self) -> bool {
+        self.supertypes.contains(&CardSupertype::Legendary)
+    }
+
+    /// Permanents are the cards which are put onto the battlefield when resolved
+    pub fn is_permanent(&self) -> bool {
+        !self.is_type(&CardType::Instant) && !self.is_type(&CardType::Sorcery)
+    }
+
+    pub fn has_color(&self, color: &Color) -> bool {
+        self.colors().contains(color)
+    }
+
+    pub fn is_colorless(&self) -> bool {
+        self.colors().is_empty()
+    }
+
+    pub fn is_monocolored(&self) -> bool {
+        self.colors().len() == 1
+    }
+
+    pub fn is_multicolored(&self) -> bool {
+        self.colors().len() > 1
+    }
+
+    /// Returns the color identity of this card used for deck validation.
+    ///
+    /// Color identity includes the colors of the card and the colors of all
+    /// mana symbols in its abilities.
+    pub fn color_identity(&self) -> IndexSet<Color> {
+        let mut cost = self.cost.get_mana_cost().unwrap_or_default();
+        let mut mana = Mana::new();
+        if let Some(play) = &self.play_ability {
+            mana += play.effect.get_mana();
+        }
+        for ability in self.activated_abilities.iter() {
+            cost += ability.cost.get_mana_cost().unwrap_or_default();
+            mana += ability.effect.get_mana();
+        }
+        for ability in self.triggered_abilities.iter() {
+            mana += ability.effect.get_mana();
+        }
+
+        let mut identity = cost.colors();
+        identity.extend(mana.colors());
+        if let Some(colors) = &self.color_indicator {
+            identity.extend(colors.iter());
+        }
+        COLORS
+            .into_iter()
+            .filter(|color| identity.contains(color))
+            .collect()
+    }
+
+    /// Determines whether this card can be included in a deck with the specified color identity.
+    pub fn fits_color_identity(&self, identity: &IndexSet<Color>) -> bool {
+        self.color_identity().is_subset(identity)
+    }
+
+    /// Replaces the static abilities granted to this permanent by its attachments.
+    /// Abilities the permanent already has are not granted again.
+    pub fn set_granted_abilities(&mut self, abilities: IndexSet<StaticAbility>) {
+        for ability in self.state.granted_abilities.drain(..) {
+            self.static_abilities.shift_remove(&ability);
+        }
+        for ability in abilities {
+            if self.static_abilities.insert(ability) {
+                self.state.granted_abilities.insert(ability);
+            }
+        }
+    }
+
+    /// Resets the state of this card when it moves to another zone.
+    pub fn reset(&mut self) {
+        self.set_granted_abilities(IndexSet::new());
+        self.state.reset();
+    }
+
     pub fn tap(&mut self) -> bool {
         if self.zone == Zone::Battlefield && !self.state.tapped.current {
             self.state.tapped.current = true;
@@ -133,7 +266,29 @@ pub fn untap_card(game: &mut Game, card_id: ObjectId, source: Option<ObjectId>)
 }
 
 pub fn put_on_battlefield(game: &mut Game, card_id: ObjectId) {
-    change_zone(game, card_id, Zone::Battlefield)
+    change_zone(game, card_id, Zone::Battlefield);
+
+    let owner_id = if let Some(card) = game.get_card(card_id) {
+        card.owner_id
+    } else {
+        return;
+    };
+    dispatch_event(
+        game,
+        Event::EnterBattlefield(CardEvent {
+            owner: owner_id,
+            card: card_id,
+            source: None,
+        }),
+    );
+}
+
+/// Creates the token from the card and puts it onto the battlefield.
+pub fn create_token(game: &mut Game, mut card: Card) -> ObjectId {
+    card.token = true;
+    let card_id = game.add_card(card);
+    put_on_battlefield(game, card_id);
+    card_id
 }
 
 pub fn put_on_graveyard(game: &mut Game, card_id: ObjectId) {
@@ -148,12 +303,12 @@ pub fn put_in_hand(game: &mut Game, card_id: ObjectId) {
     change_zone(game, card_id, Zone::Hand)
 }
 
+pub fn put_in_exile(game: &mut Game, card_id: ObjectId) {
+    change_zone(game, card_id, Zone::Exile)
+}
+
 pub fn draw_card(game: &mut Game, player_id: ObjectId) -> Option<ObjectId> {
-    let player = if let Some(player) = game.get_player(player_id) {
-        player
-    } else {
-        return None;
-    };
+    let player = game.get_player(player_id)?;
 
     let card_id = if let Some(card_id) = player.library.pop() {
         card_id
@@ -172,40 +327,48 @@ pub fn draw_card(game: &mut Game, player_id: ObjectId) -> Option<ObjectId> {
         }),
     );
 
-    return Some(card_id);
+    Some(card_id)
 }
 
 pub fn put_on_deck_top(game: &mut Game, card_id: ObjectId, player_id: ObjectId) {
+    leave_battlefield(game, card_id, &Zone::Library);
+    let is_token = game.cards.get(&card_id).is_some_and(|card| card.token);
     if let Some(player) = game.get_player(player_id) {
         for (_, cards) in player.zones_mut() {
             cards.shift_remove(&card_id);
         }
-        player.library.insert(card_id);
+        if !is_token {
+            player.library.insert(card_id);
+        }
     } else {
         return;
     };
 
     if let Some(card) = game.get_card(card_id) {
         card.zone = Zone::Library;
-        card.state.reset();
+        card.reset();
     } else {
         panic!("Card {card_id} does not exist.");
     };
 }
 
 pub fn put_on_deck_bottom(game: &mut Game, card_id: ObjectId, player_id: ObjectId) {
+    leave_battlefield(game, card_id, &Zone::Library);
+    let is_token = game.cards.get(&card_id).is_some_and(|card| card.token);
     if let Some(player) = game.get_player(player_id) {
         for (_, cards) in player.zones_mut() {
             cards.shift_remove(&card_id);
         }
-        player.library.shift_insert(0, card_id);
+        if !is_token {
+            player.library.shift_insert(0, card_id);
+        }
     } else {
         return;
     };
 
     if let Some(card) = game.get_card(card_id) {
         card.zone = Zone::Library;
-        card.state.reset();
+        card.reset();
     } else {
         panic!("Card {card_id} does not exist.");
     };
@@ -223,12 +386,41 @@ pub fn shuffle_deck(game: &mut Game, player_id: ObjectId) {
     }
 }
 
+/// Dispatches the event of the permanent leaving the battlefield before it is put into the zone,
+/// so its own abilities still trigger, and keeps its last known information.
+fn leave_battlefield(game: &mut Game, card_id: ObjectId, zone: &Zone) {
+    let card = match game.get_card(card_id) {
+        Some(card) if card.zone == Zone::Battlefield && *zone != Zone::Battlefield => card.clone(),
+        _ => return,
+    };
+    let owner_id = card.owner_id;
+    game.last_known.insert(card_id, card);
+    dispatch_event(
+        game,
+        Event::LeaveBattlefield(ZoneEvent {
+            owner: owner_id,
+            card: card_id,
+            zone: zone.clone(),
+        }),
+    );
+}
+
 fn change_zone(game: &mut Game, card_id: ObjectId, zone: Zone) {
+    leave_battlefield(game, card_id, &zone);
+
     let player_id;
+    let is_token;
     if let Some(card) = game.get_card(card_id) {
         card.zone = zone.clone();
-        card.state.reset();
+        card.reset();
         player_id = card.owner_id;
+        is_token = card.token;
+
+        if zone == Zone::Battlefield && card.is_type(&CardType::Planeswalker) {
+            // Planeswalkers enter the battlefield with their printed loyalty
+            let loyalty = card.loyalty;
+            card.state.add_counters(Counter::Loyalty, loyalty);
+        }
     } else {
         return;
     }
@@ -237,7 +429,8 @@ fn change_zone(game: &mut Game, card_id: ObjectId, zone: Zone) {
 
     if let Some(player) = game.get_player(player_id) {
         for (player_zone, cards) in player.zones_mut() {
-            if player_zone == zone {
+            // Tokens are never put into a library, they cease to exist instead
+            if player_zone == zone && !(is_token && zone == Zone::Library) {
                 cards.insert(card_id);
             } else {
                 cards.shift_remove(&card_id);
@@ -246,7 +439,7 @@ fn change_zone(game: &mut Game, card_id: ObjectId, zone: Zone) {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Zone {
     #[default]
     None,
@@ -255,9 +448,10 @@ pub enum Zone {
     Library,
     Hand,
     Stack,
+    Exile,
 }
 
-#[derive(Clone, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum CardType {
     #[default]
     Land,
@@ -266,9 +460,18 @@ pub enum CardType {
     Creature,
     Instant,
     Sorcery,
+    Planeswalker,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum CardSupertype {
+    Legendary,
+    Basic,
+    Snow,
+    World,
 }
 
-#[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum CardSubtype {
     #[default]
     None,
@@ -284,14 +487,50 @@ pub enum CardSubtype {
     Bird,
     Human,
     Spider,
+
+    Aura,
+    Equipment,
+    Treasure,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Counter {
+    /// +1/+1 counter
+    Plus,
+
+    /// -1/-1 counter
+    Minus,
+
+    /// Counter without its own meaning used by abilities of the card
+    Charge,
+
+    /// Loyalty counter of a planeswalker
+    Loyalty,
 }
 
-#[derive(Clone, Default, PartialEq, PartialOrd)]
+#[derive(Clone, Default, PartialEq)]
 pub struct CardState {
     pub power: Value<i16>,
     pub toughness: Value<i16>,
     pub summoning_sickness: Value<bool>,
     pub tapped: Value<bool>,
+
+    /// Damage marked on this creature during the turn
+    pub damage: i16,
+
+    pub counters: IndexMap<Counter, u16>,
+
+    /// Colors of the card changed by an effect until end of turn
+    pub colors: Option<IndexSet<Color>>,
+
+    /// Permanent this aura or equipment is attached to
+    pub attached_to: Option<ObjectId>,
+
+    /// Power and toughness modifier of the auras and equipment attached to this creature
+    pub modifier: (i16, i16),
+
+    /// Static abilities granted by the auras and equipment attached to this permanent
+    pub granted_abilities: IndexSet<StaticAbility>,
 }
 
 impl CardState {
@@ -308,13 +547,35 @@ impl CardState {
             toughness: Value::new(toughness),
             summoning_sickness: Value::new(true),
             tapped: Value::new(false),
+            damage: 0,
+            counters: IndexMap::new(),
+            colors: None,
+            attached_to: None,
+            modifier: (0, 0),
+            granted_abilities: IndexSet::new(),
         }
     }
 
-    /// Restores power and toughness of this creature to its default values.
+    /// Restores power and toughness of this creature to its default values
+    /// modified by the counters put on it and its attachments, removes the marked
+    /// damage and ends the effects that changed its colors.
     pub fn restore(&mut self) {
         self.power.reset();
         self.toughness.reset();
+        self.damage = 0;
+        self.colors = None;
+
+        let modifier =
+            self.get_counter(Counter::Plus) as i16 - self.get_counter(Counter::Minus) as i16;
+        self.power.current += modifier + self.modifier.0;
+        self.toughness.current += modifier + self.modifier.1;
+    }
+
+    /// Replaces the power and toughness modifier of the attachments.
+    pub fn set_modifier(&mut self, modifier: (i16, i16)) {
+        self.power.current += modifier.0 - self.modifier.0;
+        self.toughness.current += modifier.1 - self.modifier.1;
+        self.modifier = modifier;
     }
 
     /// Resets the current state to the default state of this creature
@@ -323,21 +584,124 @@ impl CardState {
         self.toughness.reset();
         self.summoning_sickness.reset();
         self.tapped.reset();
+        self.damage = 0;
+        self.counters.clear();
+        self.colors = None;
+        self.attached_to = None;
+        self.modifier = (0, 0);
+        self.granted_abilities.clear();
+    }
+
+    pub fn get_counter(&self, counter: Counter) -> u16 {
+        *self.counters.get(&counter).unwrap_or(&0)
+    }
+
+    pub fn add_counters(&mut self, counter: Counter, amount: u16) {
+        let current = self.get_counter(counter);
+        self.counters.insert(counter, current + amount);
+
+        match counter {
+            Counter::Plus => {
+                self.power.current += amount as i16;
+                self.toughness.current += amount as i16;
+            }
+            Counter::Minus => {
+                self.power.current -= amount as i16;
+                self.toughness.current -= amount as i16;
+            }
+            Counter::Charge | Counter::Loyalty => {}
+        }
+    }
+
+    /// Removes the counters from this card. Returns false if the card does not have enough counters.
+    pub fn remove_counters(&mut self, counter: Counter, amount: u16) -> bool {
+        let current = self.get_counter(counter);
+        if current < amount {
+            return false;
+        }
+        self.counters.insert(counter, current - amount);
+
+        match counter {
+            Counter::Plus => {
+                self.power.current -= amount as i16;
+                self.toughness.current -= amount as i16;
+            }
+            Counter::Minus => {
+                self.power.current += amount as i16;
+                self.toughness.current += amount as i16;
+            }
+            Counter::Charge | Counter::Loyalty => {}
+        }
+        true
+    }
+
+    /// Marks the damage on this creature reducing its current toughness.
+    pub fn mark_damage(&mut self, damage: i16) {
+        self.damage += damage;
+        self.toughness.current -= damage;
+    }
+
+    /// Toughness of this creature without the marked damage.
+    pub fn toughness_without_damage(&self) -> i16 {
+        self.toughness.current + self.damage
     }
 }
 
 pub fn is_alive(game: &mut Game, card_id: ObjectId) -> bool {
     if let Some(card) = game.get_card(card_id) {
-        return card.kind == CardType::Creature && card.state.toughness.current > 0;
+        return card.is_creature() && card.state.toughness.current > 0;
     }
     false
 }
 
+/// Returns the devotion of the player to the color, which is the number of mana symbols
+/// of that color in the mana costs of permanents the player controls.
+pub fn get_devotion(game: &mut Game, player_id: ObjectId, color: &Color) -> u8 {
+    let battlefield = if let Some(player) = game.get_player(player_id) {
+        player.battlefield.clone()
+    } else {
+        return 0;
+    };
+
+    battlefield
+        .iter()
+        .filter_map(|card_id| game.cards.get(card_id))
+        .map(|card| card.cost.get_mana_cost().unwrap_or_default().count(color))
+        .sum()
+}
+
+/// Destroys the permanent by putting it into its owner's graveyard.
+/// Returns false if the permanent is indestructible.
+pub fn destroy_card(game: &mut Game, card_id: ObjectId) -> bool {
+    if let Some(card) = game.get_card(card_id) {
+        if card.zone != Zone::Battlefield
+            || card
+                .static_abilities
+                .contains(&StaticAbility::Indestructible)
+        {
+            return false;
+        }
+    } else {
+        return false;
+    }
+
+    put_on_graveyard(game, card_id);
+    true
+}
+
 #[cfg(test)]
 mod tests {
+    use indexmap::IndexSet;
+
     use crate::{
-        card::{draw_card, put_on_deck_bottom, put_on_deck_top, Card},
+        abilities::{ActivatedAbility, Cost, Effect, Target},
+        card::{
+            draw_card, get_devotion, put_in_hand, put_on_battlefield, put_on_deck_bottom,
+            put_on_deck_top, Card,
+        },
         game::{Game, GameStatus},
+        mana::{Color, Mana, COLORS},
+        turn::cleanup_step,
     };
 
     #[test]
@@ -389,4 +753,91 @@ mod tests {
         assert_eq!(result, None);
         assert_eq!(game.status, GameStatus::Lose(player_id));
     }
+
+    #[test]
+    fn test_colors() {
+        let mut card = Card::new_creature(0, 2, 2);
+        card.cost = Cost::Mana("1WU");
+        assert_eq!(card.colors(), IndexSet::from([Color::White, Color::Blue]));
+        assert!(card.is_multicolored());
+        assert!(!card.has_color(&Color::Black));
+
+        card.cost = Cost::Mana("2R");
+        assert!(card.is_monocolored());
+        assert!(card.has_color(&Color::Red));
+
+        card.cost = Cost::Mana("4");
+        assert!(card.is_colorless());
+    }
+
+    #[test]
+    fn test_color_indicator() {
+        let mut card = Card::new_sorcery(0);
+        card.color_indicator = Some(IndexSet::from([Color::Blue]));
+        assert!(card.has_color(&Color::Blue));
+        assert!(card.is_monocolored());
+    }
+
+    #[test]
+    fn test_color_changing_effect() {
+        let (mut game, player_id, _) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.cost = Cost::Mana("G");
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+
+        let card = game.get_card(card_id).unwrap();
+        card.state.colors = Some(IndexSet::from([Color::Black]));
+        assert!(card.has_color(&Color::Black));
+        assert!(!card.has_color(&Color::Green));
+
+        cleanup_step(&mut game);
+
+        let card = game.get_card(card_id).unwrap();
+        assert!(card.has_color(&Color::Green));
+    }
+
+    #[test]
+    fn test_color_identity() {
+        let mut card = Card::new_creature(0, 1, 1);
+        card.cost = Cost::Mana("G");
+        card.activated_abilities.push(ActivatedAbility {
+            cost: Cost::Mana("1W"),
+            effect: Effect::Mana(Mana::from("U")),
+            target: Target::None,
+            restrictions: vec![],
+        });
+
+        let identity = card.color_identity();
+        assert_eq!(
+            identity,
+            IndexSet::from([Color::White, Color::Blue, Color::Green])
+        );
+        assert!(card.fits_color_identity(&IndexSet::from(COLORS)));
+        assert!(!card.fits_color_identity(&IndexSet::from([Color::Green, Color::Blue])));
+    }
+
+    #[test]
+    fn test_devotion() {
+        let (mut game, player_id, _) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.cost = Cost::Mana("1BB");
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+
+        let mut card = Card::new_enchantment(player_id);
+        card.cost = Cost::Mana("BG");
+        let card_id = game.add_card(card);
+        put_on_battlefield(&mut game, card_id);
+
+        let mut card = Card::new_creature(player_id, 1, 1);
+        card.cost = Cost::Mana("B");
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+
+        assert_eq!(get_devotion(&mut game, player_id, &Color::Black), 3);
+        assert_eq!(get_devotion(&mut game, player_id, &Color::Green), 1);
+    }
 }
@@ -3,6 +3,7 @@ use crate::abilities::ResolveKind::Ability;
 use crate::{
     abilities::{Condition, Target},
     action::Action,
+    card::Zone,
     game::{Game, ObjectId},
     turn::Step,
 };
@@ -12,46 +13,64 @@ pub enum Event {
     Tap(CardEvent),
     Untap(CardEvent),
     Draw(CardEvent),
+    EnterBattlefield(CardEvent),
+    LeaveBattlefield(ZoneEvent),
     Phase(PhaseEvent),
 }
 
 impl Event {
-    /// Defines if this event meets the trigger condition.
-    pub fn meets(&self, condition: &Condition) -> bool {
+    /// Defines if this event meets the trigger condition of the card controlled by the player.
+    pub fn meets(
+        &self,
+        game: &mut Game,
+        condition: &Condition,
+        card_id: ObjectId,
+        player_id: ObjectId,
+    ) -> bool {
+        // Whether the card of the event matches the target of the condition
+        let mut matches = |target: &Target, event_card: ObjectId| match target {
+            Target::Source => event_card == card_id,
+            Target::Card => true,
+            target => target
+                .get_filter()
+                .is_some_and(|filter| filter.matches(game, event_card, card_id, player_id)),
+        };
+
         match self {
             Event::Tap(event) => {
-                if let Condition::Tap(target) = condition {
-                    match target {
-                        Target::Source => {
-                            if let Some(source) = event.source {
-                                source == event.card
-                            } else {
-                                false
-                            }
-                        }
-                        _ => false,
+                if let Condition::Tap(Target::Source) = condition {
+                    if let Some(source) = event.source {
+                        source == event.card
+                    } else {
+                        false
                     }
                 } else {
                     false
                 }
             }
             Event::Untap(event) => {
-                if let Condition::Untap(target) = condition {
-                    match target {
-                        Target::Source => {
-                            if let Some(source) = event.source {
-                                source == event.card
-                            } else {
-                                false
-                            }
-                        }
-                        _ => false,
+                if let Condition::Untap(Target::Source) = condition {
+                    if let Some(source) = event.source {
+                        source == event.card
+                    } else {
+                        false
                     }
                 } else {
                     false
                 }
             }
             Event::Draw(_) => condition == &Condition::Draw,
+            Event::EnterBattlefield(event) => match condition {
+                Condition::EnterBattlefield(target) => matches(target, event.card),
+                _ => false,
+            },
+            Event::LeaveBattlefield(event) => match condition {
+                Condition::LeaveBattlefield(target) => matches(target, event.card),
+                Condition::Dies(target) => {
+                    event.zone == Zone::Graveyard && matches(target, event.card)
+                }
+                _ => false,
+            },
             Event::Phase(event) => {
                 if let Condition::Phase(phase) = condition {
                     phase == &event.phase
@@ -75,6 +94,18 @@ pub struct CardEvent {
     pub card: ObjectId,
 }
 
+#[derive(Debug)]
+pub struct ZoneEvent {
+    /// The player whos card changed its zone
+    pub owner: ObjectId,
+
+    /// The card that changed its zone
+    pub card: ObjectId,
+
+    /// The zone the card was put into
+    pub zone: Zone,
+}
+
 #[derive(Debug)]
 pub struct PhaseEvent {
     pub owner: ObjectId,
@@ -106,7 +137,7 @@ fn run_player_triggers(game: &mut Game, player_id: ObjectId, event: &Event) {
         };
 
         for trigger in triggers.iter() {
-            if event.meets(&trigger.condition) {
+            if event.meets(game, &trigger.condition, card_id, player_id) {
                 let mut action = Action::new(player_id, card_id);
                 action.set_required_target(trigger.target.clone());
 
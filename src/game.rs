@@ -2,10 +2,10 @@ use std::collections::HashMap;
 
 use indexmap::IndexSet;
 
-use crate::abilities::Resolve;
+use crate::abilities::{check_attachments, Resolve};
 use crate::{
-    card::{Card, Zone},
-    mana::Mana,
+    card::{destroy_card, is_alive, put_on_graveyard, Card, CardType, Counter, Zone},
+    mana::{Mana, ManaPool},
     turn::Turn,
 };
 
@@ -16,6 +16,9 @@ pub struct Game {
     pub(crate) players: Vec<Player>,
     pub(crate) cards: HashMap<usize, Card>,
     pub(crate) stack: Vec<Resolve>,
+
+    /// Last known information of the permanents as they last existed on the battlefield
+    pub(crate) last_known: HashMap<ObjectId, Card>,
     uid: ObjectId,
 }
 
@@ -50,12 +53,9 @@ impl Game {
     }
 
     pub fn get_player(&mut self, player_id: ObjectId) -> Option<&mut Player> {
-        for player in self.players.iter_mut() {
-            if player.id == player_id {
-                return Some(player);
-            }
-        }
-        None
+        self.players
+            .iter_mut()
+            .find(|player| player.id == player_id)
     }
 
     pub fn get_player_ids(&self) -> Vec<ObjectId> {
@@ -66,6 +66,21 @@ impl Game {
             .clone()
     }
 
+    /// Returns the players in APNAP order, starting with the active player
+    /// and continuing in turn order.
+    pub fn get_apnap_player_ids(&self) -> Vec<ObjectId> {
+        let player_ids = self.get_player_ids();
+        let active = player_ids
+            .iter()
+            .position(|player_id| *player_id == self.turn.active_player)
+            .unwrap_or(0);
+        player_ids[active..]
+            .iter()
+            .chain(player_ids[..active].iter())
+            .cloned()
+            .collect()
+    }
+
     pub fn get_next_player(&mut self, player_id: ObjectId) -> ObjectId {
         let mut found = false;
 
@@ -95,6 +110,17 @@ impl Game {
     pub fn get_card(&mut self, card_id: ObjectId) -> Option<&mut Card> {
         self.cards.get_mut(&card_id)
     }
+
+    /// Removes the object from the game and from the zones of its owner.
+    pub fn remove_card(&mut self, card_id: ObjectId) -> Option<Card> {
+        let card = self.cards.remove(&card_id)?;
+        if let Some(player) = self.get_player(card.owner_id) {
+            for (_, cards) in player.zones_mut() {
+                cards.shift_remove(&card_id);
+            }
+        }
+        Some(card)
+    }
 }
 
 impl Default for Game {
@@ -105,6 +131,7 @@ impl Default for Game {
             stack: vec![],
             players: vec![],
             cards: HashMap::new(),
+            last_known: HashMap::new(),
             turn: Turn::new(0),
             resolve: None,
         }
@@ -114,12 +141,14 @@ impl Default for Game {
 pub struct Player {
     pub id: ObjectId,
     pub life: i16,
-    pub mana: Mana,
+    pub poison: u16,
+    pub mana: ManaPool,
 
     pub library: IndexSet<ObjectId>,
     pub hand: IndexSet<ObjectId>,
     pub battlefield: IndexSet<ObjectId>,
     pub graveyard: IndexSet<ObjectId>,
+    pub exile: IndexSet<ObjectId>,
 
     pub hand_size_limit: Value<usize>,
 
@@ -131,16 +160,21 @@ pub const DEFAULT_HAND_SIZE: usize = 7;
 pub const DEFAULT_PLAYER_LIFE: i16 = 20;
 pub const DEFAULT_LAND_LIMIT: usize = 1;
 
+/// Defines how many poison counters a player can have before losing the game
+pub const POISON_LIMIT: u16 = 10;
+
 impl Player {
     pub fn new() -> Player {
         Player {
             id: 0,
             life: DEFAULT_PLAYER_LIFE,
-            mana: Mana::new(),
+            poison: 0,
+            mana: ManaPool::new(),
             library: IndexSet::new(),
             hand: IndexSet::new(),
             battlefield: IndexSet::new(),
             graveyard: IndexSet::new(),
+            exile: IndexSet::new(),
             hand_size_limit: Value::new(DEFAULT_HAND_SIZE),
             land_limit: Value::new(DEFAULT_LAND_LIMIT),
         }
@@ -152,6 +186,7 @@ impl Player {
             (Zone::Hand, &self.hand),
             (Zone::Battlefield, &self.battlefield),
             (Zone::Graveyard, &self.graveyard),
+            (Zone::Exile, &self.exile),
         ]
     }
 
@@ -161,10 +196,17 @@ impl Player {
             (Zone::Hand, &mut self.hand),
             (Zone::Battlefield, &mut self.battlefield),
             (Zone::Graveyard, &mut self.graveyard),
+            (Zone::Exile, &mut self.exile),
         ]
     }
 }
 
+impl Default for Player {
+    fn default() -> Self {
+        Player::new()
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct Value<T: Clone + Copy + Default + PartialEq + PartialOrd> {
     pub current: T,
@@ -186,15 +228,118 @@ impl<T: Copy + Default + PartialEq + PartialOrd> Value<T> {
 
 pub fn add_mana(game: &mut Game, player_id: ObjectId, mana: Mana) {
     if let Some(player) = game.get_player(player_id) {
-        player.mana += mana;
+        player.mana.add(mana);
+    }
+}
+
+/// Performs state-based actions for permanents on the battlefield:
+/// - auras attached illegally are put into the graveyard and equipment becomes unattached;
+/// - creatures with zero or less toughness are put into their owner's graveyard;
+/// - creatures with lethal damage are destroyed;
+/// - planeswalkers with no loyalty are put into their owner's graveyard;
+/// - the legend rule is applied to legendary permanents with the same name;
+/// - tokens which left the battlefield cease to exist.
+///
+/// The actions are checked again until none of them is performed, so that equipment becomes
+/// unattached from creatures which died and creatures die when they lose the bonus of an aura.
+pub fn check_state_based_actions(game: &mut Game) {
+    while perform_state_based_actions(game) {}
+}
+
+/// Performs all state-based actions once and returns whether any of them was performed.
+fn perform_state_based_actions(game: &mut Game) -> bool {
+    let mut performed = check_attachments(game);
+
+    let creatures: Vec<ObjectId> = game
+        .cards
+        .values()
+        .filter(|card| card.zone == Zone::Battlefield && card.is_creature())
+        .map(|card| card.id)
+        .collect();
+
+    for card_id in creatures {
+        if is_alive(game, card_id) {
+            continue;
+        }
+
+        let has_toughness = if let Some(card) = game.get_card(card_id) {
+            card.state.toughness_without_damage() > 0
+        } else {
+            continue;
+        };
+
+        // Indestructible creatures with lethal damage stay on the battlefield
+        if has_toughness {
+            performed |= destroy_card(game, card_id);
+        } else {
+            put_on_graveyard(game, card_id);
+            performed = true;
+        }
+    }
+
+    // Planeswalkers with no loyalty are put into their owner's graveyard
+    let planeswalkers: Vec<ObjectId> = game
+        .cards
+        .values()
+        .filter(|card| {
+            card.zone == Zone::Battlefield
+                && card.is_type(&CardType::Planeswalker)
+                && card.state.get_counter(Counter::Loyalty) == 0
+        })
+        .map(|card| card.id)
+        .collect();
+
+    performed |= !planeswalkers.is_empty();
+    for card_id in planeswalkers {
+        put_on_graveyard(game, card_id);
+    }
+
+    performed |= apply_legend_rule(game);
+
+    // Tokens which left the battlefield cease to exist
+    let tokens: Vec<ObjectId> = game
+        .cards
+        .values()
+        .filter(|card| card.token && card.zone != Zone::Battlefield)
+        .map(|card| card.id)
+        .collect();
+
+    performed |= !tokens.is_empty();
+    for card_id in tokens {
+        game.remove_card(card_id);
+    }
+    performed
+}
+
+/// If a player controls two or more legendary permanents with the same name, all of them
+/// except the one that entered the battlefield last are put into their owners' graveyards.
+/// Returns whether any permanent was put into a graveyard.
+fn apply_legend_rule(game: &mut Game) -> bool {
+    let mut duplicates = vec![];
+    for player in game.players.iter() {
+        let mut names = HashMap::new();
+        for card_id in player.battlefield.iter().rev() {
+            if let Some(card) = game.cards.get(card_id) {
+                if card.is_legendary() && names.insert(card.name.clone(), *card_id).is_some() {
+                    duplicates.push(*card_id);
+                }
+            }
+        }
+    }
+
+    let applied = !duplicates.is_empty();
+    for card_id in duplicates {
+        put_on_graveyard(game, card_id);
     }
+    applied
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         abilities::deal_player_damage,
-        game::{Game, GameStatus, Player},
+        card::{put_on_battlefield, Card, CardSupertype, Zone},
+        game::{check_state_based_actions, Game, GameStatus, Player},
     };
 
     #[test]
@@ -204,10 +349,52 @@ mod tests {
         player.life = 3;
 
         let player_id = game.add_player(player);
-        deal_player_damage(&mut game, player_id, 3);
+        deal_player_damage(&mut game, 0, player_id, 3);
 
         let player = game.get_player(player_id).unwrap();
         assert_eq!(player.life, 0);
         assert_eq!(game.status, GameStatus::Lose(player_id));
     }
+
+    #[test]
+    fn test_legend_rule() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card_ids = vec![];
+        for owner_id in [player_id, player_id, opponent_id] {
+            let mut card = Card::new_creature(owner_id, 2, 2);
+            card.name = "Isamaru".to_owned();
+            card.supertypes.insert(CardSupertype::Legendary);
+            let card_id = game.add_card(card);
+            put_on_battlefield(&mut game, card_id);
+            card_ids.push(card_id);
+        }
+        check_state_based_actions(&mut game);
+
+        // Only the newest permanent with the same name is kept by each player
+        let zones: Vec<Zone> = card_ids
+            .iter()
+            .map(|card_id| game.get_card(*card_id).unwrap().zone.clone())
+            .collect();
+        assert_eq!(
+            zones,
+            vec![Zone::Graveyard, Zone::Battlefield, Zone::Battlefield]
+        );
+    }
+
+    #[test]
+    fn test_apnap_order() {
+        let (mut game, player_id, opponent_id) = Game::new();
+        let third_id = game.add_player(Player::new());
+        assert_eq!(
+            game.get_apnap_player_ids(),
+            vec![player_id, opponent_id, third_id]
+        );
+
+        game.turn.active_player = opponent_id;
+        assert_eq!(
+            game.get_apnap_player_ids(),
+            vec![opponent_id, third_id, player_id]
+        );
+    }
 }
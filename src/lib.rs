@@ -4,4 +4,5 @@ pub mod card;
 pub mod events;
 pub mod game;
 pub mod mana;
+pub mod payment;
 pub mod turn;
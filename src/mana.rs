@@ -1,9 +1,15 @@
-use std::ops::{self, AddAssign, SubAssign};
+use std::{
+    fmt,
+    ops::{self, AddAssign, SubAssign},
+    str::FromStr,
+};
 
+use indexmap::IndexSet;
 use phf::phf_map;
-use regex::Regex;
 
-#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+use crate::{card::CardType, game::ObjectId};
+
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub enum Color {
     Colorless,
     White,
@@ -14,6 +20,15 @@ pub enum Color {
     Any,
 }
 
+/// The five colors of Magic in WUBRG order
+pub const COLORS: [Color; 5] = [
+    Color::White,
+    Color::Blue,
+    Color::Black,
+    Color::Red,
+    Color::Green,
+];
+
 const COLOR_CODES: phf::Map<char, Color> = phf_map! {
     'R' => Color::Red,
     'W' => Color::White,
@@ -86,44 +101,74 @@ impl Mana {
         self.get(color) > 0
     }
 
+    /// Returns the colors of this mana in WUBRG order excluding colorless mana.
+    pub fn colors(&self) -> IndexSet<Color> {
+        COLORS.into_iter().filter(|color| self.has(color)).collect()
+    }
+
     /// Returns the converted mana cost.
     pub fn cmc(&self) -> u8 {
         self.iter().iter().map(|(_, amount)| amount).sum()
     }
 
+    /// Determines whether this mana contains at least the specified amount of every color.
+    pub fn contains(&self, mana: &Mana) -> bool {
+        mana.iter()
+            .into_iter()
+            .chain([(Color::Any, mana.any)])
+            .all(|(color, amount)| self.get(&color) >= amount)
+    }
+
     /// Determines whether this mana is enough for paying the specified mana cost.
-    pub fn enough(&self, mana: &Mana) -> bool {
-        let mut remainder = self.clone();
-        for (color, amount) in mana.iter() {
-            match color {
-                Color::Colorless | Color::Any => {
-                    let colorless = remainder.get(&Color::Colorless);
-                    if colorless >= amount {
-                        remainder.set(&Color::Colorless, colorless - amount);
-                    } else {
-                        remainder.set(&Color::Colorless, 0);
-                        return remainder.pick_any(amount - colorless).is_some();
-                    }
-                }
-                Color::White | Color::Blue | Color::Black | Color::Green | Color::Red => {
-                    let current = remainder.get(&color);
-                    if current >= amount {
-                        remainder.set(&color, current - amount);
-                    } else {
-                        return false;
-                    }
-                }
-            }
+    pub fn enough(&self, cost: impl Into<ManaCost>) -> bool {
+        self.pay(&cost.into()).is_some()
+    }
+
+    /// Pays the mana cost with this mana and returns the remaining mana or None
+    /// if this mana is not enough.
+    ///
+    /// Phyrexian symbols that cannot be paid with mana are expected to be paid with life
+    /// and X is paid as zero, use `ManaCost::with_x` to define its value. Costs with snow
+    /// symbols cannot be paid since the mana does not keep whether it came from a snow source.
+    pub fn pay(&self, cost: &ManaCost) -> Option<Mana> {
+        self.pay_with_life(cost).map(|(remainder, _)| remainder)
+    }
+
+    /// Pays the mana cost with this mana and returns the remaining mana together with
+    /// the life required for the Phyrexian symbols that could not be paid with mana.
+    pub fn pay_with_life(&self, cost: &ManaCost) -> Option<(Mana, u16)> {
+        let mut symbols = cost.symbols.clone();
+        symbols.sort_by_key(|symbol| symbol.payment_order());
+        pay_symbols(*self, &symbols)
+    }
+
+    fn take(&self, color: &Color, amount: u8) -> Option<Mana> {
+        let current = self.get(color);
+        if current >= amount {
+            let mut remainder = *self;
+            remainder.set(color, current - amount);
+            Some(remainder)
+        } else {
+            None
         }
-        true
+    }
+
+    fn take_any(&self, amount: u8) -> Option<Mana> {
+        if amount == 0 {
+            return Some(*self);
+        }
+        self.pick_any(amount).map(|pick| *self - pick)
     }
 
     /// Picks any amount of available mana and creates a new instance with the selection.
+    /// Colorless mana is picked first, then the colors with the most mana available.
     pub fn pick_any(&self, amount: u8) -> Option<Mana> {
         let mut pick = Mana::new();
         let mut remainder = amount;
 
-        for (color, current) in self.iter() {
+        let mut available = self.iter();
+        available.sort_by_key(|(color, current)| (*color != Color::Colorless, u8::MAX - current));
+        for (color, current) in available {
             if current == 0 {
                 continue;
             } else if current > remainder {
@@ -174,37 +219,458 @@ impl<const N: usize> From<[(Color, u8); N]> for Mana {
     }
 }
 
+/// Life paid instead of mana for a single Phyrexian symbol.
+pub const PHYREXIAN_LIFE: u16 = 2;
+
+/// Pays the mana symbols sorted from the most to the least restrictive one
+/// trying every option of the hybrid and Phyrexian symbols and keeping the one
+/// that requires the least life.
+fn pay_symbols(pool: Mana, symbols: &[ManaSymbol]) -> Option<(Mana, u16)> {
+    let (symbol, rest) = if let Some(split) = symbols.split_first() {
+        split
+    } else {
+        return Some((pool, 0));
+    };
+
+    let pay_rest = |pool: Mana| pay_symbols(pool, rest);
+    match symbol {
+        ManaSymbol::Generic(amount) => pool.take_any(*amount).and_then(pay_rest),
+        ManaSymbol::Colored(color) => pool.take(color, 1).and_then(pay_rest),
+        ManaSymbol::Colorless => pool.take(&Color::Colorless, 1).and_then(pay_rest),
+        ManaSymbol::Hybrid(first, second) => {
+            least_life(pool.take(first, 1).and_then(pay_rest), || {
+                pool.take(second, 1).and_then(pay_rest)
+            })
+        }
+        ManaSymbol::MonoHybrid(color) => least_life(pool.take(color, 1).and_then(pay_rest), || {
+            pool.take_any(2).and_then(pay_rest)
+        }),
+        ManaSymbol::Phyrexian(color) => least_life(pool.take(color, 1).and_then(pay_rest), || {
+            pay_rest(pool).map(|(pool, life)| (pool, life + PHYREXIAN_LIFE))
+        }),
+        // Snow sources of the mana are not tracked, so snow symbols cannot be paid
+        ManaSymbol::Snow => None,
+        ManaSymbol::X => pay_rest(pool),
+    }
+}
+
+/// Returns the payment that requires less life, the other option is tried only if
+/// the first one cannot be paid or requires life.
+fn least_life(
+    first: Option<(Mana, u16)>,
+    second: impl FnOnce() -> Option<(Mana, u16)>,
+) -> Option<(Mana, u16)> {
+    match first {
+        Some((_, 0)) => first,
+        Some((_, life)) => match second() {
+            Some(other) if other.1 < life => Some(other),
+            _ => first,
+        },
+        None => second(),
+    }
+}
+
+impl FromStr for Mana {
+    type Err = ParseManaError;
+
+    /// Parses the mana in the brace notation `{2}{U}{U}` or the compact notation `2UU`.
+    /// Generic symbols define the amount of colorless mana and `*` defines mana of any color.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut mana = Mana::new();
+        for symbol in split_symbols(value)? {
+            let (color, amount) = if symbol == "*" {
+                (Color::Any, 1)
+            } else {
+                match symbol.parse::<ManaSymbol>()? {
+                    ManaSymbol::Generic(amount) => (Color::Colorless, amount),
+                    ManaSymbol::Colorless => (Color::Colorless, 1),
+                    ManaSymbol::Colored(color) => (color, 1),
+                    _ => return Err(ParseManaError::InvalidSymbol(symbol)),
+                }
+            };
+            mana.set(&color, mana.get(&color).saturating_add(amount));
+        }
+        Ok(mana)
+    }
+}
+
 impl From<&str> for Mana {
+    /// Creates the mana from its notation.
+    ///
+    /// Panics if the notation is malformed, use `str::parse` to handle the error.
     fn from(value: &str) -> Self {
-        let cmc_regex = Regex::new(r"(?<colorless>\d*)(?<colored>[WUBRG*]*)").unwrap();
-        match cmc_regex.captures(value) {
-            Some(matched) => {
-                let (_, [colorless, colored]) = matched.extract();
-
-                let mut mana = Mana::new();
-                if let Ok(colorless_amount) = colorless.parse::<u8>() {
-                    mana.set(&Color::Colorless, colorless_amount);
+        match value.parse() {
+            Ok(mana) => mana,
+            Err(err) => panic!("Invalid mana {value:?}: {err}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseManaError {
+    InvalidSymbol(String),
+    UnclosedBrace,
+}
+
+impl fmt::Display for ParseManaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseManaError::InvalidSymbol(symbol) => write!(f, "invalid mana symbol {symbol:?}"),
+            ParseManaError::UnclosedBrace => write!(f, "unclosed brace"),
+        }
+    }
+}
+
+impl std::error::Error for ParseManaError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ManaSymbol {
+    /// Can be paid with mana of any type, e.g. `{3}`
+    Generic(u8),
+
+    /// Can be paid only with mana of the specified color, e.g. `{U}`
+    Colored(Color),
+
+    /// Can be paid only with colorless mana, `{C}`
+    Colorless,
+
+    /// Can be paid with mana of either color, e.g. `{W/U}`
+    Hybrid(Color, Color),
+
+    /// Can be paid with mana of the specified color or two generic mana, e.g. `{2/B}`
+    MonoHybrid(Color),
+
+    /// Can be paid with mana of the specified color or two life, e.g. `{R/P}`
+    Phyrexian(Color),
+
+    /// Defines the amount of generic mana chosen by the player, `{X}`
+    X,
+
+    /// Can be paid with mana from a snow source, `{S}`
+    ///
+    /// Snow sources of mana are not tracked yet, so costs with this symbol cannot be paid.
+    Snow,
+}
+
+impl ManaSymbol {
+    /// Returns the mana value of this symbol.
+    pub fn cmc(&self) -> u8 {
+        match self {
+            ManaSymbol::Generic(amount) => *amount,
+            ManaSymbol::MonoHybrid(_) => 2,
+            ManaSymbol::X => 0,
+            _ => 1,
+        }
+    }
+
+    /// Returns the colors of this symbol.
+    pub fn colors(&self) -> Vec<Color> {
+        match self {
+            ManaSymbol::Colored(color)
+            | ManaSymbol::MonoHybrid(color)
+            | ManaSymbol::Phyrexian(color) => vec![*color],
+            ManaSymbol::Hybrid(first, second) => vec![*first, *second],
+            _ => vec![],
+        }
+    }
+
+    fn payment_order(&self) -> u8 {
+        match self {
+            ManaSymbol::Colored(_) => 0,
+            ManaSymbol::Colorless => 1,
+            ManaSymbol::Hybrid(_, _) => 2,
+            ManaSymbol::Phyrexian(_) => 3,
+            ManaSymbol::MonoHybrid(_) => 4,
+            ManaSymbol::Snow => 5,
+            ManaSymbol::Generic(_) => 6,
+            ManaSymbol::X => 7,
+        }
+    }
+}
+
+impl FromStr for ManaSymbol {
+    type Err = ParseManaError;
+
+    /// Parses the content of a single mana symbol without braces, e.g. `2`, `U`, `W/U` or `R/P`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(amount) = value.parse::<u8>() {
+            return Ok(ManaSymbol::Generic(amount));
+        }
+
+        let invalid = || ParseManaError::InvalidSymbol(value.to_string());
+        let parts: Vec<&str> = value.split('/').collect();
+        let symbol = match parts.as_slice() {
+            ["X"] => ManaSymbol::X,
+            ["S"] => ManaSymbol::Snow,
+            ["C"] => ManaSymbol::Colorless,
+            [color] => ManaSymbol::Colored(parse_color(color).ok_or_else(invalid)?),
+            ["2", color] => ManaSymbol::MonoHybrid(parse_color(color).ok_or_else(invalid)?),
+            [color, "P"] => ManaSymbol::Phyrexian(parse_color(color).ok_or_else(invalid)?),
+            [first, second] => {
+                let first = parse_color(first).ok_or_else(invalid)?;
+                let second = parse_color(second).ok_or_else(invalid)?;
+                if first == second {
+                    return Err(invalid());
                 }
+                ManaSymbol::Hybrid(first, second)
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(symbol)
+    }
+}
+
+impl fmt::Display for ManaSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManaSymbol::Generic(amount) => write!(f, "{{{amount}}}"),
+            ManaSymbol::Colored(color) => write!(f, "{{{}}}", color_code(color)),
+            ManaSymbol::Colorless => write!(f, "{{C}}"),
+            ManaSymbol::Hybrid(first, second) => {
+                write!(f, "{{{}/{}}}", color_code(first), color_code(second))
+            }
+            ManaSymbol::MonoHybrid(color) => write!(f, "{{2/{}}}", color_code(color)),
+            ManaSymbol::Phyrexian(color) => write!(f, "{{{}/P}}", color_code(color)),
+            ManaSymbol::X => write!(f, "{{X}}"),
+            ManaSymbol::Snow => write!(f, "{{S}}"),
+        }
+    }
+}
+
+/// Mana cost of a spell or an ability defined by its mana symbols.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ManaCost {
+    pub symbols: Vec<ManaSymbol>,
+}
+
+impl ManaCost {
+    pub fn new() -> ManaCost {
+        ManaCost { symbols: vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns the mana value of this cost, X is counted as zero.
+    pub fn cmc(&self) -> u8 {
+        self.symbols.iter().map(|symbol| symbol.cmc()).sum()
+    }
+
+    /// Returns the colors of the mana symbols in WUBRG order.
+    pub fn colors(&self) -> IndexSet<Color> {
+        COLORS
+            .into_iter()
+            .filter(|color| self.count(color) > 0)
+            .collect()
+    }
+
+    /// Returns the number of mana symbols of the specified color.
+    pub fn count(&self, color: &Color) -> u8 {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.colors().contains(color))
+            .count() as u8
+    }
+
+    pub fn has_x(&self) -> bool {
+        self.symbols.contains(&ManaSymbol::X)
+    }
+
+    /// Creates a new cost without the specified mana symbol, a generic symbol reduces
+    /// the generic mana by its amount. Returns None if the cost does not contain the symbol.
+    pub fn without_symbol(&self, symbol: &ManaSymbol) -> Option<ManaCost> {
+        let mut cost = self.clone();
+        if let ManaSymbol::Generic(amount) = symbol {
+            let generic: u8 = cost
+                .symbols
+                .iter()
+                .map(|symbol| match symbol {
+                    ManaSymbol::Generic(amount) => *amount,
+                    _ => 0,
+                })
+                .sum();
+            if generic < *amount {
+                return None;
+            }
+            return Some(cost.with_generic_change(-(*amount as i16)));
+        }
+
+        let index = cost.symbols.iter().position(|current| current == symbol)?;
+        cost.symbols.remove(index);
+        Some(cost)
+    }
+
+    /// Creates a new cost with the generic mana increased or reduced by the specified amount.
+    /// Reduction applies only to generic mana and cannot reduce it below zero.
+    pub fn with_generic_change(&self, change: i16) -> ManaCost {
+        let mut cost = self.clone();
+        if change > 0 {
+            let increase = change as u8;
+            match cost.symbols.iter_mut().find_map(|symbol| match symbol {
+                ManaSymbol::Generic(amount) => Some(amount),
+                _ => None,
+            }) {
+                Some(amount) => *amount = amount.saturating_add(increase),
+                None => cost.symbols.insert(0, ManaSymbol::Generic(increase)),
+            }
+            return cost;
+        }
+
+        let mut reduction = change.unsigned_abs() as u8;
+        for symbol in cost.symbols.iter_mut().rev() {
+            if let ManaSymbol::Generic(amount) = symbol {
+                let reduced = reduction.min(*amount);
+                *amount -= reduced;
+                reduction -= reduced;
+            }
+        }
+        cost.symbols
+            .retain(|symbol| *symbol != ManaSymbol::Generic(0));
+        cost
+    }
+
+    /// Creates a new cost replacing X with the specified amount of generic mana.
+    pub fn with_x(&self, x: u8) -> ManaCost {
+        ManaCost {
+            symbols: self
+                .symbols
+                .iter()
+                .map(|symbol| match symbol {
+                    ManaSymbol::X => ManaSymbol::Generic(x),
+                    symbol => *symbol,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for ManaCost {
+    type Err = ParseManaError;
+
+    /// Parses the mana cost in the brace notation `{2}{U}{U}` or the compact notation `2UU`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let symbols = split_symbols(value)?
+            .iter()
+            .map(|symbol| symbol.parse())
+            .collect::<Result<Vec<ManaSymbol>, ParseManaError>>()?;
+        Ok(ManaCost { symbols })
+    }
+}
+
+impl TryFrom<&str> for ManaCost {
+    type Error = ParseManaError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for ManaCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for symbol in self.symbols.iter() {
+            write!(f, "{symbol}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&Mana> for ManaCost {
+    /// Creates the cost that requires the specified mana, colorless mana and mana of
+    /// any color are treated as generic.
+    fn from(mana: &Mana) -> Self {
+        let mut symbols = vec![];
+        let generic = mana.colorless + mana.any;
+        if generic > 0 {
+            symbols.push(ManaSymbol::Generic(generic));
+        }
+        for color in COLORS {
+            for _ in 0..mana.get(&color) {
+                symbols.push(ManaSymbol::Colored(color));
+            }
+        }
+        ManaCost { symbols }
+    }
+}
+
+impl From<&ManaCost> for ManaCost {
+    fn from(cost: &ManaCost) -> Self {
+        cost.clone()
+    }
+}
+
+impl ops::Add<ManaCost> for ManaCost {
+    type Output = ManaCost;
+
+    fn add(self, rhs: ManaCost) -> Self::Output {
+        let mut result = self;
+        result.add_assign(rhs);
+        result
+    }
+}
+
+impl ops::AddAssign<ManaCost> for ManaCost {
+    fn add_assign(&mut self, rhs: ManaCost) {
+        self.symbols.extend(rhs.symbols);
+    }
+}
+
+/// Splits the mana notation into the symbols. Supports both the brace notation `{2}{U}{U}`
+/// and the compact notation `2UU`.
+fn split_symbols(value: &str) -> Result<Vec<String>, ParseManaError> {
+    let mut symbols = vec![];
+    let mut chars = value.trim().chars().peekable();
 
-                for char in colored.chars().into_iter() {
-                    if let Some(color) = COLOR_CODES.get(&char) {
-                        let amount = mana.get(color);
-                        mana.set(color, amount + 1);
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => {
+                let mut symbol = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => symbol.push(char),
+                        None => return Err(ParseManaError::UnclosedBrace),
                     }
                 }
-
-                mana
+                symbols.push(symbol);
             }
-            None => Mana::new(),
+            '0'..='9' => {
+                let mut number = String::from(char);
+                while let Some(digit) = chars.next_if(|char| char.is_ascii_digit()) {
+                    number.push(digit);
+                }
+                symbols.push(number);
+            }
+            _ => symbols.push(char.to_string()),
         }
     }
+    Ok(symbols)
+}
+
+fn parse_color(code: &str) -> Option<Color> {
+    let mut chars = code.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => COLOR_CODES
+            .get(&char)
+            .filter(|color| COLORS.contains(color))
+            .copied(),
+        _ => None,
+    }
+}
+
+fn color_code(color: &Color) -> char {
+    COLOR_CODES
+        .entries()
+        .find(|(_, code_color)| *code_color == color)
+        .map(|(code, _)| *code)
+        .unwrap_or('?')
 }
 
 impl ops::Add<Mana> for Mana {
     type Output = Mana;
 
     fn add(self, rhs: Mana) -> Self::Output {
-        let mut result = self.clone();
+        let mut result = self;
         result.add_assign(rhs);
         result
     }
@@ -223,7 +689,7 @@ impl ops::Sub<Mana> for Mana {
     type Output = Mana;
 
     fn sub(self, rhs: Mana) -> Self::Output {
-        let mut result = self.clone();
+        let mut result = self;
         result.sub_assign(rhs);
         result
     }
@@ -238,9 +704,182 @@ impl ops::SubAssign<Mana> for Mana {
     }
 }
 
+/// Defines what the mana can be spent on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManaUsage {
+    /// Casting a spell with the card types
+    Spell(IndexSet<CardType>),
+    Ability,
+}
+
+/// Restricts the spending of the mana in a mana pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ManaRestriction {
+    /// Can be spent only to cast spells of the card type
+    Spell(CardType),
+
+    /// Can be spent only to activate abilities
+    Ability,
+}
+
+impl ManaRestriction {
+    pub fn allows(&self, usage: &ManaUsage) -> bool {
+        match (self, usage) {
+            (ManaRestriction::Spell(kind), ManaUsage::Spell(types)) => types.contains(kind),
+            (ManaRestriction::Ability, ManaUsage::Ability) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Defines when the unspent mana is removed from a mana pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ManaDuration {
+    /// Mana is removed at the end of the step
+    #[default]
+    Step,
+
+    /// Mana is removed at the end of the turn
+    Turn,
+}
+
+/// Mana in a mana pool produced by the same source with the same restrictions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ManaEntry {
+    pub mana: Mana,
+    pub source_id: Option<ObjectId>,
+    pub restriction: Option<ManaRestriction>,
+    pub duration: ManaDuration,
+}
+
+impl ManaEntry {
+    pub fn new(mana: Mana) -> ManaEntry {
+        ManaEntry {
+            mana,
+            ..Default::default()
+        }
+    }
+
+    pub fn can_spend(&self, usage: &ManaUsage) -> bool {
+        self.restriction
+            .is_none_or(|restriction| restriction.allows(usage))
+    }
+
+    fn same_kind(&self, entry: &ManaEntry) -> bool {
+        self.source_id == entry.source_id
+            && self.restriction == entry.restriction
+            && self.duration == entry.duration
+    }
+}
+
+/// Mana pool of a player which keeps the source and restrictions of the mana.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ManaPool {
+    pub entries: Vec<ManaEntry>,
+}
+
+impl ManaPool {
+    pub fn new() -> ManaPool {
+        ManaPool { entries: vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total().cmc() == 0
+    }
+
+    /// Adds unrestricted mana which is removed at the end of the step.
+    pub fn add(&mut self, mana: Mana) {
+        self.add_entry(ManaEntry::new(mana));
+    }
+
+    pub fn add_entry(&mut self, entry: ManaEntry) {
+        if let Some(current) = self.entries.iter_mut().find(|e| e.same_kind(&entry)) {
+            current.mana += entry.mana;
+        } else {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Returns all mana in the pool regardless of its restrictions.
+    pub fn total(&self) -> Mana {
+        self.entries
+            .iter()
+            .fold(Mana::new(), |total, entry| total + entry.mana)
+    }
+
+    /// Returns the mana which can be spent on the specified usage.
+    pub fn available(&self, usage: &ManaUsage) -> Mana {
+        self.entries
+            .iter()
+            .filter(|entry| entry.can_spend(usage))
+            .fold(Mana::new(), |total, entry| total + entry.mana)
+    }
+
+    /// Removes the mana from the pool and returns the spent entries or None if the mana
+    /// is not available for the usage. Restricted mana and mana removed at the end of
+    /// the step are spent first.
+    pub fn spend(&mut self, mana: &Mana, usage: &ManaUsage) -> Option<Vec<ManaEntry>> {
+        if !self.available(usage).contains(mana) {
+            return None;
+        }
+
+        let mut remainder = *mana;
+        let mut spent = vec![];
+        let mut order: Vec<usize> = (0..self.entries.len())
+            .filter(|index| self.entries[*index].can_spend(usage))
+            .collect();
+        order.sort_by_key(|index| {
+            let entry = &self.entries[*index];
+            (
+                entry.restriction.is_none(),
+                entry.duration == ManaDuration::Turn,
+            )
+        });
+
+        for index in order {
+            let entry = &mut self.entries[index];
+            let mut taken = Mana::new();
+            for (color, amount) in remainder.iter() {
+                taken.set(&color, amount.min(entry.mana.get(&color)));
+            }
+            if taken.cmc() == 0 {
+                continue;
+            }
+            entry.mana -= taken;
+            remainder -= taken;
+            spent.push(ManaEntry {
+                mana: taken,
+                ..*entry
+            });
+        }
+
+        self.entries.retain(|entry| entry.mana.cmc() > 0);
+        Some(spent)
+    }
+
+    /// Removes the mana which lasts only until the end of the step.
+    pub fn empty_step(&mut self) {
+        self.entries
+            .retain(|entry| entry.duration != ManaDuration::Step);
+    }
+
+    /// Removes all mana from the pool.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::mana::{Color, Mana};
+    use indexmap::IndexSet;
+
+    use crate::{
+        card::CardType,
+        mana::{
+            Color, Mana, ManaCost, ManaDuration, ManaEntry, ManaPool, ManaRestriction, ManaSymbol,
+            ManaUsage, ParseManaError,
+        },
+    };
 
     #[test]
     fn test_color() {
@@ -300,4 +939,209 @@ mod tests {
         assert_eq!(Mana::from("1U").cmc(), 2);
         assert_eq!(Mana::from("2WWBB").cmc(), 6);
     }
+
+    #[test]
+    fn test_parse_braces() {
+        let cost: ManaCost = "{2}{U}{U}".parse().unwrap();
+        assert_eq!(
+            cost.symbols,
+            vec![
+                ManaSymbol::Generic(2),
+                ManaSymbol::Colored(Color::Blue),
+                ManaSymbol::Colored(Color::Blue)
+            ]
+        );
+        assert_eq!(cost, "2UU".parse().unwrap());
+        assert_eq!(Mana::from("{1}{C}{R}"), Mana::from("2R"));
+    }
+
+    #[test]
+    fn test_parse_special_symbols() {
+        let cost = ManaCost::try_from("{X}{W/U}{2/B}{R/P}{S}{C}").unwrap();
+        assert_eq!(
+            cost.symbols,
+            vec![
+                ManaSymbol::X,
+                ManaSymbol::Hybrid(Color::White, Color::Blue),
+                ManaSymbol::MonoHybrid(Color::Black),
+                ManaSymbol::Phyrexian(Color::Red),
+                ManaSymbol::Snow,
+                ManaSymbol::Colorless
+            ]
+        );
+        assert_eq!(cost.cmc(), 6);
+        assert_eq!(
+            cost.colors().into_iter().collect::<Vec<_>>(),
+            vec![Color::White, Color::Blue, Color::Black, Color::Red]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "{2}{U".parse::<ManaCost>(),
+            Err(ParseManaError::UnclosedBrace)
+        );
+        assert_eq!(
+            "{Q}".parse::<ManaCost>(),
+            Err(ParseManaError::InvalidSymbol("Q".to_string()))
+        );
+        assert_eq!(
+            "{U/U}".parse::<ManaCost>(),
+            Err(ParseManaError::InvalidSymbol("U/U".to_string()))
+        );
+        assert!("{W/U}".parse::<Mana>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let notation = "{X}{10}{G/W}{2/R}{B/P}{C}{S}{U}";
+        let cost: ManaCost = notation.parse().unwrap();
+        assert_eq!(cost.to_string(), notation);
+        assert_eq!(cost.to_string().parse::<ManaCost>().unwrap(), cost);
+    }
+
+    #[test]
+    fn test_enough_specific_colorless() {
+        let cost: ManaCost = "{C}".parse().unwrap();
+        assert!(Mana::from("C").enough(&cost));
+        assert!(!Mana::from("R").enough(&cost));
+    }
+
+    #[test]
+    fn test_enough_hybrid() {
+        let cost: ManaCost = "{W/U}{W}".parse().unwrap();
+        assert!(Mana::from("WU").enough(&cost));
+        assert!(Mana::from("WW").enough(&cost));
+        assert!(!Mana::from("UU").enough(&cost));
+
+        let cost: ManaCost = "{2/B}".parse().unwrap();
+        assert!(Mana::from("B").enough(&cost));
+        assert!(Mana::from("RG").enough(&cost));
+        assert!(!Mana::from("R").enough(&cost));
+    }
+
+    #[test]
+    fn test_pay_phyrexian_with_life() {
+        let cost: ManaCost = "{1}{R/P}".parse().unwrap();
+        assert_eq!(
+            Mana::from("RR").pay_with_life(&cost),
+            Some((Mana::new(), 0))
+        );
+        assert_eq!(Mana::from("R").pay_with_life(&cost), Some((Mana::new(), 2)));
+        assert_eq!(Mana::new().pay_with_life(&cost), None);
+    }
+
+    #[test]
+    fn test_pay_phyrexian_with_mana_before_hybrid() {
+        let cost: ManaCost = "{2/W}{W/P}".parse().unwrap();
+        assert_eq!(
+            Mana::from("WW1").pay_with_life(&cost),
+            Some((Mana::from("1"), 0))
+        );
+        assert_eq!(
+            Mana::from("W2").pay_with_life(&cost),
+            Some((Mana::new(), 0))
+        );
+        assert_eq!(Mana::from("W").pay_with_life(&cost), Some((Mana::new(), 2)));
+
+        let cost: ManaCost = "{W/U}{W/P}".parse().unwrap();
+        assert_eq!(
+            Mana::from("WU").pay_with_life(&cost),
+            Some((Mana::new(), 0))
+        );
+    }
+
+    #[test]
+    fn test_snow_cannot_be_paid() {
+        let cost: ManaCost = "{S}{G}".parse().unwrap();
+        assert!(!Mana::from("GG").enough(&cost));
+        assert!(Mana::from("GG").enough(&Mana::from("1G")));
+    }
+
+    #[test]
+    fn test_with_x() {
+        let cost: ManaCost = "{X}{R}".parse().unwrap();
+        assert!(cost.has_x());
+        assert!(Mana::from("R").enough(&cost));
+        assert!(!Mana::from("R").enough(cost.with_x(2)));
+        assert!(Mana::from("3R").enough(cost.with_x(2)));
+        assert_eq!(cost.with_x(2).cmc(), 3);
+    }
+
+    #[test]
+    fn test_pool_restrictions() {
+        let mut pool = ManaPool::new();
+        pool.add(Mana::from("R"));
+        pool.add_entry(ManaEntry {
+            restriction: Some(ManaRestriction::Spell(CardType::Creature)),
+            ..ManaEntry::new(Mana::from("G"))
+        });
+
+        assert_eq!(pool.total(), Mana::from("RG"));
+        assert_eq!(
+            pool.available(&ManaUsage::Spell(IndexSet::from([CardType::Creature]))),
+            Mana::from("RG")
+        );
+        assert_eq!(
+            pool.available(&ManaUsage::Spell(IndexSet::from([CardType::Sorcery]))),
+            Mana::from("R")
+        );
+        assert_eq!(pool.available(&ManaUsage::Ability), Mana::from("R"));
+        assert_eq!(pool.spend(&Mana::from("G"), &ManaUsage::Ability), None);
+
+        let artifact_creature = IndexSet::from([CardType::Artifact, CardType::Creature]);
+        assert_eq!(
+            pool.available(&ManaUsage::Spell(artifact_creature)),
+            Mana::from("RG")
+        );
+    }
+
+    #[test]
+    fn test_pool_spends_restricted_mana_first() {
+        let mut pool = ManaPool::new();
+        pool.add_entry(ManaEntry {
+            source_id: Some(1),
+            ..ManaEntry::new(Mana::from("G"))
+        });
+        pool.add_entry(ManaEntry {
+            source_id: Some(2),
+            restriction: Some(ManaRestriction::Spell(CardType::Creature)),
+            ..ManaEntry::new(Mana::from("G"))
+        });
+
+        let spent = pool
+            .spend(
+                &Mana::from("G"),
+                &ManaUsage::Spell(IndexSet::from([CardType::Creature])),
+            )
+            .unwrap();
+        assert_eq!(spent.len(), 1);
+        assert_eq!(spent[0].source_id, Some(2));
+        assert_eq!(pool.available(&ManaUsage::Ability), Mana::from("G"));
+    }
+
+    #[test]
+    fn test_pool_duration() {
+        let mut pool = ManaPool::new();
+        pool.add(Mana::from("U"));
+        pool.add_entry(ManaEntry {
+            duration: ManaDuration::Turn,
+            ..ManaEntry::new(Mana::from("B"))
+        });
+
+        pool.empty_step();
+        assert_eq!(pool.total(), Mana::from("B"));
+
+        pool.clear();
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_generic_change() {
+        let cost: ManaCost = "{2}{G}".parse().unwrap();
+        assert_eq!(cost.with_generic_change(1).cmc(), 4);
+        assert_eq!(cost.with_generic_change(-1), "{1}{G}".parse().unwrap());
+        assert_eq!(cost.with_generic_change(-3), "{G}".parse().unwrap());
+    }
 }
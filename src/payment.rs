@@ -0,0 +1,479 @@
+use crate::{
+    abilities::{can_activate_ability, create_ability_action, play_ability, Cost, Effect, Target},
+    action::{Action, Choice},
+    card::Zone,
+    game::{Game, ObjectId},
+    mana::{Color, Mana, ManaCost, ManaSymbol, ManaUsage, COLORS, PHYREXIAN_LIFE},
+};
+
+/// Permanent with mana abilities which can be activated to pay a mana cost.
+/// All abilities of a source require tapping it, so only one of them can be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManaSource {
+    pub card_id: ObjectId,
+
+    /// Mana abilities of the source with the mana they produce. Abilities producing
+    /// mana of any color are listed once for every color.
+    pub abilities: Vec<(usize, Mana)>,
+}
+
+/// Mana ability chosen to be activated for a payment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManaActivation {
+    pub card_id: ObjectId,
+    pub ability_id: usize,
+    pub mana: Mana,
+}
+
+/// Assignment of the player's mana pool and mana sources to a mana cost.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Payment {
+    /// Mana abilities which have to be activated before paying
+    pub activations: Vec<ManaActivation>,
+
+    /// Mana spent from the pool after the activations
+    pub mana: Mana,
+
+    /// Life paid for the Phyrexian symbols
+    pub life: u16,
+}
+
+/// Returns the untapped permanents of the player that can be tapped for mana.
+pub fn get_mana_sources(game: &mut Game, player_id: ObjectId) -> Vec<ManaSource> {
+    let battlefield = if let Some(player) = game.get_player(player_id) {
+        player.battlefield.clone()
+    } else {
+        return vec![];
+    };
+
+    battlefield
+        .iter()
+        .filter_map(|card_id| game.cards.get(card_id))
+        .filter(|card| {
+            card.zone == Zone::Battlefield
+                && card.owner_id == player_id
+                && !card.state.tapped.current
+                // Creatures can be tapped for their ability only if they don't have summoning sickness
+                && (!card.is_creature() || !card.state.summoning_sickness.current)
+        })
+        .filter_map(|card| {
+            let abilities: Vec<(usize, Mana)> = card
+                .activated_abilities
+                .iter()
+                .enumerate()
+                .filter(|(_, ability)| ability.cost == Cost::Tap(Target::Source))
+                .flat_map(|(ability_id, ability)| match &ability.effect {
+                    Effect::Mana(mana) => get_mana_options(mana)
+                        .into_iter()
+                        .map(|mana| (ability_id, mana))
+                        .collect(),
+                    _ => vec![],
+                })
+                .collect();
+
+            if abilities.is_empty() {
+                None
+            } else {
+                Some(ManaSource {
+                    card_id: card.id,
+                    abilities,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Returns every mana that can be produced by the mana effect, choosing a single color
+/// for the mana of any color.
+fn get_mana_options(mana: &Mana) -> Vec<Mana> {
+    if !mana.has(&Color::Any) {
+        return vec![*mana];
+    }
+
+    COLORS
+        .iter()
+        .map(|color| {
+            let mut option = *mana;
+            option.set(&Color::Any, 0);
+            option.set(color, option.get(color) + mana.any);
+            option
+        })
+        .collect()
+}
+
+/// Finds the payment of the mana cost with the fewest mana sources, using the mana
+/// pool of the player first. Phyrexian symbols are paid with mana whenever possible
+/// and with no more life than the player has. Returns None if the player cannot pay the cost.
+pub fn find_payment(
+    game: &mut Game,
+    player_id: ObjectId,
+    cost: &ManaCost,
+    usage: &ManaUsage,
+) -> Option<Payment> {
+    let player = game.get_player(player_id)?;
+    let pool = player.mana.available(usage);
+    let life_total = player.life;
+    let mut sources = get_mana_sources(game, player_id);
+    // Interchangeable sources are grouped together to skip equivalent combinations
+    sources.sort_by_key(source_key);
+
+    let phyrexian = cost
+        .symbols
+        .iter()
+        .filter(|symbol| matches!(symbol, ManaSymbol::Phyrexian(_)))
+        .count() as u16;
+    for paid_with_life in 0..=phyrexian {
+        let max_life = paid_with_life * PHYREXIAN_LIFE;
+        if max_life as i16 > life_total {
+            break;
+        }
+
+        for count in 0..=sources.len() {
+            let mut activations = vec![];
+            if let Some(payment) =
+                find_activations(pool, cost, &sources, count, max_life, &mut activations)
+            {
+                return Some(payment);
+            }
+        }
+    }
+    None
+}
+
+fn source_key(source: &ManaSource) -> Vec<(u8, u8, u8, u8, u8, u8)> {
+    source
+        .abilities
+        .iter()
+        .map(|(_, mana)| {
+            (
+                mana.white,
+                mana.blue,
+                mana.black,
+                mana.red,
+                mana.green,
+                mana.colorless,
+            )
+        })
+        .collect()
+}
+
+/// Chooses exactly `count` of the sources and one of their abilities so that
+/// the produced mana together with the pool pays the cost with at most `max_life` life.
+fn find_activations(
+    pool: Mana,
+    cost: &ManaCost,
+    sources: &[ManaSource],
+    count: usize,
+    max_life: u16,
+    activations: &mut Vec<ManaActivation>,
+) -> Option<Payment> {
+    if count == 0 {
+        let (remainder, life) = pool.pay_with_life(cost)?;
+        if life > max_life {
+            return None;
+        }
+        return Some(Payment {
+            activations: activations.clone(),
+            mana: pool - remainder,
+            life,
+        });
+    }
+
+    for (index, source) in sources.iter().enumerate() {
+        if sources.len() - index < count {
+            break;
+        }
+        if index > 0 && source_key(&sources[index - 1]) == source_key(source) {
+            // Using an identical source instead of the skipped one yields the same payment
+            continue;
+        }
+
+        for (ability_id, mana) in source.abilities.iter() {
+            activations.push(ManaActivation {
+                card_id: source.card_id,
+                ability_id: *ability_id,
+                mana: *mana,
+            });
+            let payment = find_activations(
+                pool + *mana,
+                cost,
+                &sources[index + 1..],
+                count - 1,
+                max_life,
+                activations,
+            );
+            activations.pop();
+
+            if payment.is_some() {
+                return payment;
+            }
+        }
+    }
+    None
+}
+
+/// Activates the mana abilities needed to pay the mana cost and returns the payment.
+/// Nothing is activated if the player cannot pay the cost or any of the mana abilities
+/// cannot be activated.
+pub fn auto_tap(
+    game: &mut Game,
+    player_id: ObjectId,
+    cost: &ManaCost,
+    usage: &ManaUsage,
+) -> Option<Payment> {
+    let payment = find_payment(game, player_id, cost, usage)?;
+
+    let mut actions = vec![];
+    for activation in payment.activations.iter() {
+        let (card_id, ability_id) = (activation.card_id, activation.ability_id);
+        let mut action = create_ability_action(game, player_id, card_id, ability_id)?;
+        action.choices.cost = Choice::Card(card_id);
+        action.choices.effect = Choice::Mana(activation.mana);
+
+        if !can_activate_ability(game, card_id, ability_id, player_id) || !action.valid(game) {
+            return None;
+        }
+        actions.push((card_id, ability_id, action));
+    }
+
+    for (card_id, ability_id, action) in actions {
+        if !play_ability(game, card_id, ability_id, action) {
+            return None;
+        }
+    }
+    Some(payment)
+}
+
+/// Taps the mana sources for the mana cost of the action left after the chosen
+/// substitutes and chooses the mana to pay with. Returns false if the player cannot
+/// pay the cost.
+pub fn auto_pay(game: &mut Game, action: &mut Action) -> bool {
+    let cost = match action.get_mana_to_pay(game) {
+        Some(cost) => cost,
+        None => return false,
+    };
+    if cost.is_empty() {
+        return true;
+    }
+
+    let payment = if let Some(payment) = auto_tap(game, action.player_id, &cost, &action.mana_usage)
+    {
+        payment
+    } else {
+        return false;
+    };
+
+    let mana = Choice::Mana(payment.mana);
+    action.choices.cost = match action.choices.cost.clone() {
+        Choice::None => mana,
+        Choice::And(mut choices) => {
+            choices.push(mana);
+            Choice::And(choices)
+        }
+        choice => Choice::And(vec![choice, mana]),
+    };
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        abilities::{
+            create_card_action, create_card_action_with_costs, play_card, ActivatedAbility,
+            AlternativeCost, Amount, CastCosts, Cost, Effect, PlayAbility, StaticAbility, Target,
+        },
+        action::Choice,
+        card::{put_in_hand, put_on_battlefield, Card},
+        game::{add_mana, Game, ObjectId},
+        mana::{Mana, ManaCost, ManaSymbol, ManaUsage},
+        payment::{auto_pay, find_payment},
+        turn::precombat_step,
+    };
+
+    fn create_land(game: &mut Game, player_id: ObjectId, mana: &[&str]) -> ObjectId {
+        let mut card = Card::new_land(player_id);
+        for mana in mana {
+            card.activated_abilities.push(ActivatedAbility {
+                cost: Cost::Tap(Target::Source),
+                effect: Effect::Mana(Mana::from(*mana)),
+                target: Target::None,
+                restrictions: vec![],
+            });
+        }
+        let card_id = game.add_card(card);
+        put_on_battlefield(game, card_id);
+        card_id
+    }
+
+    fn create_sorcery(game: &mut Game, player_id: ObjectId, cost: &'static str) -> ObjectId {
+        let mut card = Card::new_sorcery(player_id);
+        card.cost = Cost::Mana(cost);
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::Draw(Amount::Fixed(1)),
+            target: Target::Owner,
+        });
+        let card_id = game.add_card(card);
+        put_in_hand(game, card_id);
+        precombat_step(game);
+        card_id
+    }
+
+    #[test]
+    fn test_find_payment_from_pool() {
+        let (mut game, player_id, _) = Game::new();
+        add_mana(&mut game, player_id, Mana::from("UR"));
+        create_land(&mut game, player_id, &["G"]);
+
+        let cost: ManaCost = "{1}{U}".parse().unwrap();
+        let payment = find_payment(&mut game, player_id, &cost, &ManaUsage::Ability).unwrap();
+        assert!(payment.activations.is_empty());
+        assert_eq!(payment.mana, Mana::from("UR"));
+    }
+
+    #[test]
+    fn test_find_payment_keeps_colors_for_colored_symbols() {
+        let (mut game, player_id, _) = Game::new();
+        let island_id = create_land(&mut game, player_id, &["U"]);
+        let tundra_id = create_land(&mut game, player_id, &["W", "U"]);
+        let plains_id = create_land(&mut game, player_id, &["W"]);
+
+        let cost: ManaCost = "{1}{U}{U}".parse().unwrap();
+        let payment = find_payment(&mut game, player_id, &cost, &ManaUsage::Ability).unwrap();
+        let mut card_ids: Vec<ObjectId> = payment
+            .activations
+            .iter()
+            .map(|activation| activation.card_id)
+            .collect();
+        card_ids.sort();
+        let mut expected = vec![island_id, tundra_id, plains_id];
+        expected.sort();
+        assert_eq!(card_ids, expected);
+        assert_eq!(payment.mana, Mana::from("UUW"));
+
+        let cost: ManaCost = "{U}{U}{U}".parse().unwrap();
+        assert_eq!(
+            find_payment(&mut game, player_id, &cost, &ManaUsage::Ability),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_payment_with_any_color() {
+        let (mut game, player_id, _) = Game::new();
+        create_land(&mut game, player_id, &["*"]);
+        create_land(&mut game, player_id, &["R"]);
+
+        let cost: ManaCost = "{B}{R}".parse().unwrap();
+        let payment = find_payment(&mut game, player_id, &cost, &ManaUsage::Ability).unwrap();
+        assert_eq!(payment.mana, Mana::from("BR"));
+    }
+
+    #[test]
+    fn test_find_payment_prefers_mana_over_life() {
+        let (mut game, player_id, _) = Game::new();
+        let mountain_id = create_land(&mut game, player_id, &["R"]);
+
+        let cost: ManaCost = "{R/P}{R/P}".parse().unwrap();
+        let payment = find_payment(&mut game, player_id, &cost, &ManaUsage::Ability).unwrap();
+        assert_eq!(payment.activations.len(), 1);
+        assert_eq!(payment.activations[0].card_id, mountain_id);
+        assert_eq!(payment.mana, Mana::from("R"));
+        assert_eq!(payment.life, 2);
+
+        // The player cannot pay more life than they have
+        game.get_player(player_id).unwrap().life = 3;
+        let cost: ManaCost = "{R/P}{R/P}{R/P}".parse().unwrap();
+        assert_eq!(
+            find_payment(&mut game, player_id, &cost, &ManaUsage::Ability),
+            None
+        );
+    }
+
+    #[test]
+    fn test_auto_pay_spell() {
+        let (mut game, player_id, _) = Game::new();
+        let forest_id = create_land(&mut game, player_id, &["G"]);
+        let mountain_id = create_land(&mut game, player_id, &["R"]);
+        let island_id = create_land(&mut game, player_id, &["U"]);
+
+        let card_id = create_sorcery(&mut game, player_id, "1G");
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        assert!(auto_pay(&mut game, &mut action));
+        play_card(&mut game, card_id, action);
+
+        let tapped: Vec<bool> = [forest_id, mountain_id, island_id]
+            .iter()
+            .map(|card_id| game.get_card(*card_id).unwrap().state.tapped.current)
+            .collect();
+        assert_eq!(tapped.iter().filter(|tapped| **tapped).count(), 2);
+        assert!(tapped[0]);
+        assert!(game.get_player(player_id).unwrap().mana.is_empty());
+        assert_eq!(game.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_auto_pay_alternative_cost() {
+        let (mut game, player_id, _) = Game::new();
+        let plains_id = create_land(&mut game, player_id, &["W"]);
+        let forest_id = create_land(&mut game, player_id, &["G"]);
+
+        let mut card = Card::new_creature(player_id, 3, 3);
+        card.cost = Cost::Mana("1W");
+        card.play_ability = Some(PlayAbility {
+            effect: Effect::None,
+            target: Target::None,
+        });
+        card.alternative_costs
+            .push(AlternativeCost::Evoke(Cost::Mana("W")));
+        let card_id = game.add_card(card);
+        put_in_hand(&mut game, card_id);
+        precombat_step(&mut game);
+
+        let costs = CastCosts {
+            alternative: Some(AlternativeCost::Evoke(Cost::Mana("W"))),
+            ..Default::default()
+        };
+        let mut action =
+            create_card_action_with_costs(&mut game, card_id, player_id, costs).unwrap();
+        assert!(auto_pay(&mut game, &mut action));
+        play_card(&mut game, card_id, action);
+
+        assert!(game.get_card(plains_id).unwrap().state.tapped.current);
+        assert!(!game.get_card(forest_id).unwrap().state.tapped.current);
+        assert_eq!(game.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_auto_pay_with_substitutes() {
+        let (mut game, player_id, _) = Game::new();
+        let forest_id = create_land(&mut game, player_id, &["G"]);
+        let mountain_id = create_land(&mut game, player_id, &["R"]);
+        let creature_id = game.add_card(Card::new_creature(player_id, 1, 1));
+        put_on_battlefield(&mut game, creature_id);
+
+        let card_id = create_sorcery(&mut game, player_id, "1G");
+        game.get_card(card_id)
+            .unwrap()
+            .static_abilities
+            .insert(StaticAbility::Convoke);
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        action.choices.cost = Choice::Substitute(creature_id, ManaSymbol::Generic(1));
+        assert!(auto_pay(&mut game, &mut action));
+        play_card(&mut game, card_id, action);
+
+        assert!(game.get_card(forest_id).unwrap().state.tapped.current);
+        assert!(!game.get_card(mountain_id).unwrap().state.tapped.current);
+        assert_eq!(game.stack.len(), 1);
+    }
+
+    #[test]
+    fn test_auto_pay_without_enough_sources() {
+        let (mut game, player_id, _) = Game::new();
+        let forest_id = create_land(&mut game, player_id, &["G"]);
+
+        let card_id = create_sorcery(&mut game, player_id, "GG");
+        let mut action = create_card_action(&mut game, card_id, player_id).unwrap();
+        assert!(!auto_pay(&mut game, &mut action));
+        assert!(!game.get_card(forest_id).unwrap().state.tapped.current);
+    }
+}
@@ -3,10 +3,12 @@ use std::cmp;
 use indexmap::{IndexMap, IndexSet};
 
 use crate::{
-    abilities::{deal_player_damage, Effect, StaticAbility},
-    card::{draw_card, is_alive, put_on_graveyard, untap_card, CardType, Zone},
+    abilities::{
+        deal_damage, deal_player_damage, is_protected_from, Amount, Effect, StaticAbility,
+    },
+    card::{draw_card, untap_card, CardType, Zone},
     events::{dispatch_event, Event, PhaseEvent},
-    game::{Game, ObjectId, Value},
+    game::{check_state_based_actions, Game, ObjectId, Value},
 };
 
 pub struct Turn {
@@ -15,6 +17,12 @@ pub struct Turn {
     pub combat: Combat,
     pub active_player: ObjectId,
     pub lands_played: usize,
+
+    /// Activated abilities activated this turn by the card and the ability index
+    pub activated_abilities: Vec<(ObjectId, usize)>,
+
+    /// Total life gained this turn by each player
+    pub life_gained: IndexMap<ObjectId, u16>,
 }
 
 impl Turn {
@@ -25,6 +33,8 @@ impl Turn {
             combat: Combat::new(),
             active_player: player_id,
             lands_played: 0,
+            activated_abilities: vec![],
+            life_gained: IndexMap::new(),
         }
     }
 }
@@ -77,12 +87,19 @@ impl Step {
     pub fn main(&self) -> bool {
         self == &Step::Precombat || self == &Step::Postcombat
     }
+
+    pub fn combat(&self) -> bool {
+        self >= &Step::CombatBegin && self <= &Step::CombatEnd
+    }
 }
 
 #[derive(Clone)]
 pub struct Combat {
     pub attackers: IndexMap<ObjectId, Attacker>,
     blockers_toughness: IndexMap<ObjectId, i16>,
+
+    /// Combat damage each blocker assigns to the attackers it blocks
+    blockers_assignments: IndexMap<ObjectId, IndexMap<ObjectId, i16>>,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -97,6 +114,7 @@ impl Combat {
         Combat {
             attackers: IndexMap::new(),
             blockers_toughness: IndexMap::new(),
+            blockers_assignments: IndexMap::new(),
         }
     }
 
@@ -105,10 +123,21 @@ impl Combat {
     }
 
     pub fn get_blockers(&self) -> Vec<ObjectId> {
+        // Creatures blocking several attackers are listed once
         self.attackers
             .values()
-            .map(|attacker| attacker.blockers.iter().cloned())
-            .flatten()
+            .flat_map(|attacker| attacker.blockers.iter().cloned())
+            .collect::<IndexSet<ObjectId>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the attackers blocked by the creature in the order they were declared.
+    pub fn get_blocked_attackers(&self, blocker_id: ObjectId) -> Vec<ObjectId> {
+        self.attackers
+            .values()
+            .filter(|attacker| attacker.blockers.contains(&blocker_id))
+            .map(|attacker| attacker.id)
             .collect()
     }
 
@@ -120,6 +149,12 @@ impl Combat {
     }
 }
 
+impl Default for Combat {
+    fn default() -> Self {
+        Combat::new()
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Attacker {
     pub id: ObjectId,
@@ -170,7 +205,7 @@ pub fn untap_step(game: &mut Game) {
     for card_id in tapped_cards {
         untap_card(game, card_id, None);
         if let Some(card) = game.get_card(card_id) {
-            if card.kind == CardType::Creature {
+            if card.is_creature() {
                 card.state.summoning_sickness.current = false;
             }
         }
@@ -217,7 +252,7 @@ pub fn declare_attackers_step_start(game: &mut Game) {
 }
 
 pub fn declare_attacker(game: &mut Game, attacker_id: ObjectId, target: ObjectId) {
-    if can_declare_attacker(game, attacker_id) {
+    if can_declare_attacker(game, attacker_id) && can_be_attacked(game, target) {
         let mut attacks: IndexMap<AttackType, Attack>;
         if let Some(card) = game.get_card(attacker_id) {
             attacks = IndexMap::new();
@@ -277,10 +312,7 @@ pub fn can_declare_attacker(game: &mut Game, card_id: ObjectId) -> bool {
     let active_player = game.turn.active_player;
 
     if let Some(card) = game.get_card(card_id) {
-        if card.owner_id != active_player
-            || card.zone != Zone::Battlefield
-            || card.kind != CardType::Creature
-        {
+        if card.owner_id != active_player || card.zone != Zone::Battlefield || !card.is_creature() {
             return false;
         }
         if card.static_abilities.contains(&StaticAbility::Defender) {
@@ -291,6 +323,28 @@ pub fn can_declare_attacker(game: &mut Game, card_id: ObjectId) -> bool {
     false
 }
 
+/// Determines whether the target can be attacked by the active player. Creatures can attack
+/// opponents and planeswalkers controlled by opponents.
+pub fn can_be_attacked(game: &mut Game, target: ObjectId) -> bool {
+    let active_player = game.turn.active_player;
+    if let Some(card) = game.get_card(target) {
+        return card.owner_id != active_player
+            && card.zone == Zone::Battlefield
+            && card.is_type(&CardType::Planeswalker);
+    }
+    target != active_player && game.get_player(target).is_some()
+}
+
+/// Returns the player defending against the attack on the target, which is either the attacked
+/// player or the controller of the attacked planeswalker.
+pub fn get_defending_player(game: &mut Game, target: ObjectId) -> ObjectId {
+    if let Some(card) = game.get_card(target) {
+        card.owner_id
+    } else {
+        target
+    }
+}
+
 pub fn declare_attackers_step_end(game: &mut Game) {
     for attacker in game.turn.combat.attackers.clone().keys() {
         if let Some(card) = game.get_card(*attacker) {
@@ -325,11 +379,24 @@ pub fn declare_blocker(game: &mut Game, blocker_id: ObjectId, attacker_id: Objec
 
 pub fn can_declare_blocker(game: &mut Game, blocker_id: ObjectId, attacker_id: ObjectId) -> bool {
     let defending_player = if let Some(attacker) = game.turn.combat.attackers.get(&attacker_id) {
-        attacker.target.clone()
+        get_defending_player(game, attacker.target)
     } else {
         return false;
     };
 
+    let other_blocked = game
+        .turn
+        .combat
+        .get_blocked_attackers(blocker_id)
+        .into_iter()
+        .filter(|blocked_id| *blocked_id != attacker_id)
+        .count();
+
+    if is_protected_from(game, attacker_id, blocker_id) {
+        // Creatures cannot be blocked by creatures they have protection from
+        return false;
+    }
+
     let attacker_abilities = if let Some(card) = game.get_card(attacker_id) {
         card.static_abilities.clone()
     } else {
@@ -339,24 +406,33 @@ pub fn can_declare_blocker(game: &mut Game, blocker_id: ObjectId, attacker_id: O
     if let Some(blocker) = game.get_card(blocker_id) {
         if blocker.owner_id != defending_player
             || blocker.zone != Zone::Battlefield
-            || blocker.kind != CardType::Creature
+            || !blocker.is_creature()
         {
             return false;
         }
 
-        for ability in attacker_abilities.iter() {
-            match ability {
-                StaticAbility::Flying => {
-                    // Flying creatures can only be blocked by other flying creatures
-                    // or by creatures with reach
-                    if !blocker.static_abilities.contains(&StaticAbility::Flying)
-                        && !blocker.static_abilities.contains(&StaticAbility::Reach)
-                    {
-                        return false;
-                    }
-                }
-                _ => {}
-            }
+        // Flying creatures can only be blocked by other flying creatures
+        // or by creatures with reach
+        if attacker_abilities.contains(&StaticAbility::Flying)
+            && !blocker.static_abilities.contains(&StaticAbility::Flying)
+            && !blocker.static_abilities.contains(&StaticAbility::Reach)
+        {
+            return false;
+        }
+
+        // Creatures can block a single attacker unless they can block additional creatures
+        let additional: usize = blocker
+            .static_abilities
+            .iter()
+            .map(|ability| match ability {
+                StaticAbility::BlockAdditional(count) => *count as usize,
+                _ => 0,
+            })
+            .sum();
+        if !blocker.static_abilities.contains(&StaticAbility::BlockAny)
+            && other_blocked > additional
+        {
+            return false;
         }
         return true;
     }
@@ -378,7 +454,9 @@ pub fn combat_damage_step_start(game: &mut Game) {
     game.turn.step = Step::CombatDamage;
     game.turn.priority = None;
 
+    set_attackers_power(game);
     set_blockers_toughness(game);
+    set_blockers_assignments(game);
 
     let attackers = game.turn.combat.attackers.clone();
     for attack_type in &[AttackType::FirstStrike, AttackType::Regular] {
@@ -418,6 +496,23 @@ pub fn combat_damage_step_start(game: &mut Game) {
     }
 }
 
+/// Updates the power of each attack to the current power of the attacker, so that
+/// the effects applied after attackers are declared change the damage assigned.
+fn set_attackers_power(game: &mut Game) {
+    for attacker_id in game.turn.combat.get_attackers() {
+        let power = if let Some(attacker) = game.get_card(attacker_id) {
+            attacker.state.power.current
+        } else {
+            continue;
+        };
+        if let Some(attacker) = game.turn.combat.attackers.get_mut(&attacker_id) {
+            for attack in attacker.attacks.values_mut() {
+                *attack = Attack::new(power);
+            }
+        }
+    }
+}
+
 fn set_blockers_toughness(game: &mut Game) {
     let attackers = game.turn.combat.attackers.clone();
     for attacker in attackers.values() {
@@ -435,6 +530,129 @@ fn set_blockers_toughness(game: &mut Game) {
     }
 }
 
+/// Distributes the combat damage of each blocker among the attackers it blocks automatically,
+/// assigning lethal damage to each attacker in order and the rest to the last one. Blockers of
+/// a single attacker deal damage equal to their power when combat damage is dealt instead.
+fn set_blockers_assignments(game: &mut Game) {
+    for blocker_id in game.turn.combat.get_blockers() {
+        let attackers = game.turn.combat.get_blocked_attackers(blocker_id);
+        if attackers.len() < 2 {
+            continue;
+        }
+
+        let assignments = attackers
+            .into_iter()
+            .map(|attacker_id| (attacker_id, 0))
+            .collect();
+        let assignments = distribute_blocker_damage(game, blocker_id, assignments);
+        game.turn
+            .combat
+            .blockers_assignments
+            .insert(blocker_id, assignments);
+    }
+}
+
+/// Updates the damage assignments of the blockers before combat damage is dealt. Damage
+/// assigned to attackers that were removed from combat, for example by first strike damage,
+/// is redistributed among the remaining attackers, and the assignments follow the current
+/// power of the blocker.
+fn update_blockers_assignments(game: &mut Game) {
+    let blockers_assignments = game.turn.combat.blockers_assignments.clone();
+    for (blocker_id, assignments) in blockers_assignments {
+        let remaining = assignments
+            .into_iter()
+            .filter(|(attacker_id, _)| {
+                game.get_card(*attacker_id)
+                    .is_some_and(|attacker| attacker.zone == Zone::Battlefield)
+            })
+            .collect();
+        let assignments = distribute_blocker_damage(game, blocker_id, remaining);
+        game.turn
+            .combat
+            .blockers_assignments
+            .insert(blocker_id, assignments);
+    }
+}
+
+/// Adjusts the damage assigned to each attacker so that the blocker assigns all of its power.
+/// Missing damage brings each attacker in order up to lethal damage and the rest goes to the
+/// last one, excess damage is removed starting from the last attacker.
+fn distribute_blocker_damage(
+    game: &mut Game,
+    blocker_id: ObjectId,
+    mut assignments: IndexMap<ObjectId, i16>,
+) -> IndexMap<ObjectId, i16> {
+    let (power, deathtouch) = if let Some(blocker) = game.get_card(blocker_id) {
+        (
+            cmp::max(blocker.state.power.current, 0),
+            blocker
+                .static_abilities
+                .contains(&StaticAbility::Deathtouch),
+        )
+    } else {
+        return IndexMap::new();
+    };
+
+    let mut excess = assignments.values().sum::<i16>() - power;
+    for damage in assignments.values_mut().rev() {
+        if excess <= 0 {
+            break;
+        }
+        let removed = cmp::min(*damage, excess);
+        *damage -= removed;
+        excess -= removed;
+    }
+
+    let mut damage_left = cmp::max(-excess, 0);
+    let count = assignments.len();
+    for (index, (attacker_id, damage)) in assignments.iter_mut().enumerate() {
+        let lethal = match game.get_card(*attacker_id) {
+            Some(_) if deathtouch => 1,
+            Some(attacker) => attacker.state.toughness.current,
+            None => 0,
+        };
+        let added = if index + 1 == count {
+            damage_left
+        } else {
+            cmp::min(cmp::max(lethal - *damage, 0), damage_left)
+        };
+        *damage += added;
+        damage_left -= added;
+    }
+    assignments
+}
+
+/// Assigns combat damage of the blocker to one of the attackers it blocks. The damage
+/// assigned to all attackers cannot exceed the power of the blocker.
+pub fn assign_blocker_damage(
+    game: &mut Game,
+    blocker_id: ObjectId,
+    attacker_id: ObjectId,
+    damage: i16,
+) -> bool {
+    let power = match game.get_card(blocker_id) {
+        Some(blocker) => blocker.state.power.current,
+        None => return false,
+    };
+
+    if let Some(assignments) = game.turn.combat.blockers_assignments.get_mut(&blocker_id) {
+        if damage < 0 || !assignments.contains_key(&attacker_id) {
+            return false;
+        }
+        let others: i16 = assignments
+            .iter()
+            .filter(|(assigned_id, _)| **assigned_id != attacker_id)
+            .map(|(_, assigned)| assigned)
+            .sum();
+        if others + damage > power {
+            return false;
+        }
+        assignments.insert(attacker_id, damage);
+        return true;
+    }
+    false
+}
+
 pub fn reset_combat_assignments(game: &mut Game, attacker_id: ObjectId) {
     if let Some(attacker) = game.turn.combat.attackers.get_mut(&attacker_id) {
         for attack in attacker.attacks.values_mut() {
@@ -557,24 +775,9 @@ pub fn combat_damage_step_end(game: &mut Game, attack: AttackType) {
         AttackType::FirstStrike => (attackers_first, blockers_first),
     };
 
+    update_blockers_assignments(game);
     combat_damage_step(game, &mut can_attack, &mut can_block, attack);
-
-    let mut dead = vec![];
-    let attackers = game.turn.combat.attackers.clone();
-    for attacker in attackers.values() {
-        if !is_alive(game, attacker.id) {
-            dead.push(attacker.id);
-        }
-        for blocker in attacker.blockers.iter() {
-            if !is_alive(game, *blocker) {
-                dead.push(*blocker);
-            }
-        }
-    }
-
-    for card_id in dead {
-        put_on_graveyard(game, card_id);
-    }
+    check_state_based_actions(game);
 
     game.turn.priority = Some(Priority::new(game.turn.active_player));
 }
@@ -588,7 +791,8 @@ fn group_creatures_by_attack_type(
 
     for creature_id in creatures.iter() {
         if let Some(card) = game.get_card(*creature_id) {
-            if card.zone != Zone::Battlefield || card.state.toughness.current <= 0 {
+            // Creatures destroyed by the first strike damage have already left the battlefield
+            if card.zone != Zone::Battlefield {
                 continue;
             }
 
@@ -616,18 +820,16 @@ fn combat_damage_step(
     for attacker_id in attackers.iter() {
         let mut block = IndexSet::new();
         let mut trample = false;
-        let mut deathtouch_attack = false;
 
         if let Some(card) = game.get_card(*attacker_id) {
             trample = card.static_abilities.contains(&StaticAbility::Trample);
-            deathtouch_attack = card.static_abilities.contains(&StaticAbility::Deathtouch);
         }
 
         if let Some(attacker) = game.turn.combat.attackers.get_mut(attacker_id) {
             block = attacker.blockers.clone();
 
             // Creatures with trample can deal remaining damage to the defending player
-            attacker.blocked = !trample && block.len() > 0;
+            attacker.blocked = !trample && !block.is_empty();
         }
 
         for blocker_id in block.iter() {
@@ -638,48 +840,42 @@ fn combat_damage_step(
                 }
             }
 
-            let mut damage_taken = 0;
-            let mut deathtouch_block = false;
+            // Combat damage is dealt simultaneously, so the blocker deals the damage
+            // assigned to this attacker before it takes damage from the attacker
+            let assigned = game
+                .turn
+                .combat
+                .blockers_assignments
+                .get(blocker_id)
+                .map(|assignments| *assignments.get(attacker_id).unwrap_or(&0));
+            let damage_taken = match (assigned, game.get_card(*blocker_id)) {
+                (Some(damage), Some(_)) => damage,
+                (None, Some(blocker)) => blocker.state.power.current,
+                (_, None) => 0,
+            };
 
             // Blocker takes damage
-            if let Some(blocker) = game.get_card(*blocker_id) {
-                damage_taken = blocker.state.power.current;
-                if damage_dealt > 0 {
-                    deathtouch_block = blocker
-                        .static_abilities
-                        .contains(&StaticAbility::Deathtouch);
-
-                    if can_attack.contains(attacker_id) {
-                        if deathtouch_attack {
-                            // Any amount of damage dealt by deathtouch creatures is lethal
-                            blocker.state.toughness.current = 0;
-                        } else {
-                            blocker.state.toughness.current -= damage_dealt;
-                        }
-                    }
-                }
+            if damage_dealt > 0 && can_attack.contains(attacker_id) {
+                deal_damage(game, *attacker_id, *blocker_id, damage_dealt as u16);
             }
 
-            if damage_taken > 0 {
-                // Attacker takes damage
-                if let Some(attacker) = game.get_card(*attacker_id) {
-                    if can_counterattack.contains(blocker_id) {
-                        if deathtouch_block {
-                            // Any amount of damage dealt by deathtouch creatures is lethal
-                            attacker.state.toughness.current = 0;
-                        } else {
-                            attacker.state.toughness.current -= damage_taken;
-                        }
-                    }
-                }
+            // Attacker takes damage
+            if damage_taken > 0 && can_counterattack.contains(blocker_id) {
+                deal_damage(game, *blocker_id, *attacker_id, damage_taken as u16);
             }
         }
 
         if let Some(attacker) = game.turn.combat.attackers.get(attacker_id) {
             if let Some(attack) = attacker.attacks.get(&attack_type) {
                 if !attacker.blocked && can_attack.contains(attacker_id) {
-                    // Attacker is not blocked, the defending player takes the remaining damage.
-                    deal_player_damage(game, attacker.target, attack.power.current as u16);
+                    // Attacker is not blocked, the attacked player or planeswalker
+                    // takes the remaining damage.
+                    let (target, damage) = (attacker.target, attack.power.current);
+                    if game.cards.contains_key(&target) {
+                        deal_damage(game, *attacker_id, target, damage as u16);
+                    } else {
+                        deal_player_damage(game, *attacker_id, target, damage as u16);
+                    }
                 }
             }
         }
@@ -702,16 +898,19 @@ pub fn cleanup_step(game: &mut Game) -> Effect {
     game.turn.step = Step::Cleanup;
     game.turn.priority = None;
 
+    for player in game.players.iter_mut() {
+        player.mana.clear();
+    }
+
     for card in game.cards.values_mut() {
-        if card.kind == CardType::Creature {
-            card.state.restore();
-        }
+        card.state.restore();
     }
 
     if let Some(player) = game.get_player(game.turn.active_player) {
         let hand_size = player.hand.len();
         if hand_size > player.hand_size_limit.current {
-            return Effect::Discard(hand_size - player.hand_size_limit.current);
+            let count = hand_size - player.hand_size_limit.current;
+            return Effect::Discard(Amount::Fixed(count as u16));
         }
     }
     Effect::None
@@ -734,7 +933,7 @@ fn change_step(game: &mut Game, phase: Step) {
 
     // Purge mana pools of all players between phases
     for player in game.players.iter_mut() {
-        player.mana.clear();
+        player.mana.empty_step();
     }
 
     dispatch_event(
@@ -751,16 +950,16 @@ fn change_step(game: &mut Game, phase: Step) {
 mod tests {
     use crate::{
         abilities::StaticAbility,
-        card::{put_on_battlefield, Card, Zone},
+        card::{put_on_battlefield, Card, Counter, Zone},
         game::Game,
         turn::{
-            all_passed, assign_combat_damage, combat_damage_step_start, declare_blocker,
-            fast_combat, is_combat_damage_assigned, AttackType,
+            all_passed, assign_blocker_damage, assign_combat_damage, combat_damage_step_start,
+            declare_blocker, fast_combat, is_combat_damage_assigned, AttackType,
         },
     };
 
     use super::{
-        combat_damage_step_end, declare_attacker, declare_attackers_step_end,
+        can_declare_blocker, combat_damage_step_end, declare_attacker, declare_attackers_step_end,
         declare_attackers_step_start, declare_blockers_step_end, declare_blockers_step_start,
         Priority,
     };
@@ -805,6 +1004,47 @@ mod tests {
         assert_eq!(opponent.life, 17);
     }
 
+    #[test]
+    fn test_combat_attack_planeswalker() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 3, 1);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let planeswalker_id = game.add_card(Card::new_planeswalker(opponent_id, 3));
+        put_on_battlefield(&mut game, planeswalker_id);
+        let own_planeswalker_id = game.add_card(Card::new_planeswalker(player_id, 3));
+        put_on_battlefield(&mut game, own_planeswalker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 1));
+        put_on_battlefield(&mut game, blocker_id);
+
+        declare_attackers_step_start(&mut game);
+        declare_attacker(&mut game, attacker_id, own_planeswalker_id);
+        assert!(game.turn.combat.attackers.is_empty());
+
+        declare_attacker(&mut game, attacker_id, planeswalker_id);
+        declare_attackers_step_end(&mut game);
+
+        // The controller of the planeswalker is the defending player
+        declare_blockers_step_start(&mut game);
+        assert!(can_declare_blocker(&mut game, blocker_id, attacker_id));
+        declare_blockers_step_end(&mut game);
+
+        combat_damage_step_start(&mut game);
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let card = game.get_card(planeswalker_id).unwrap();
+        assert_eq!(card.state.get_counter(Counter::Loyalty), 0);
+        assert_eq!(card.zone, Zone::Graveyard);
+
+        let opponent = game.get_player(opponent_id).unwrap();
+        assert_eq!(opponent.life, 20);
+    }
+
     #[test]
     fn test_combat_with_blocker() {
         let (mut game, player_id, opponent_id) = Game::new();
@@ -861,6 +1101,256 @@ mod tests {
         assert_eq!(opponent.life, 20);
     }
 
+    #[test]
+    fn test_block_multiple_attackers() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut attackers = Vec::new();
+        for _ in 0..3 {
+            let mut card = Card::new_creature(player_id, 1, 1);
+            card.static_abilities.insert(StaticAbility::Haste);
+            let attacker_id = game.add_card(card);
+            put_on_battlefield(&mut game, attacker_id);
+            attackers.push(attacker_id);
+        }
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 1, 5));
+        put_on_battlefield(&mut game, blocker_id);
+
+        let mut card = Card::new_creature(opponent_id, 1, 5);
+        card.static_abilities
+            .insert(StaticAbility::BlockAdditional(1));
+        let additional_id = game.add_card(card);
+        put_on_battlefield(&mut game, additional_id);
+
+        let mut card = Card::new_creature(opponent_id, 1, 5);
+        card.static_abilities.insert(StaticAbility::BlockAny);
+        let any_id = game.add_card(card);
+        put_on_battlefield(&mut game, any_id);
+
+        declare_attackers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_attacker(&mut game, *attacker_id, opponent_id);
+        }
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+
+        // Creatures can block a single attacker
+        declare_blocker(&mut game, blocker_id, attackers[0]);
+        assert!(!can_declare_blocker(&mut game, blocker_id, attackers[1]));
+
+        // Creatures that can block an additional creature can block two attackers
+        declare_blocker(&mut game, additional_id, attackers[0]);
+        declare_blocker(&mut game, additional_id, attackers[1]);
+        assert!(!can_declare_blocker(&mut game, additional_id, attackers[2]));
+
+        // Creatures that can block any number of creatures can block all attackers
+        for attacker_id in attackers.iter() {
+            assert!(can_declare_blocker(&mut game, any_id, *attacker_id));
+            declare_blocker(&mut game, any_id, *attacker_id);
+        }
+
+        assert_eq!(game.turn.combat.get_blocked_attackers(any_id), attackers);
+        assert_eq!(
+            game.turn.combat.get_blockers(),
+            vec![blocker_id, additional_id, any_id]
+        );
+    }
+
+    #[test]
+    fn test_blocker_splits_combat_damage() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut attackers = Vec::new();
+        for toughness in [2, 3] {
+            let mut card = Card::new_creature(player_id, 1, toughness);
+            card.static_abilities.insert(StaticAbility::Haste);
+            let attacker_id = game.add_card(card);
+            put_on_battlefield(&mut game, attacker_id);
+            attackers.push(attacker_id);
+        }
+
+        let mut card = Card::new_creature(opponent_id, 4, 5);
+        card.static_abilities
+            .insert(StaticAbility::BlockAdditional(1));
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        declare_attackers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_attacker(&mut game, *attacker_id, opponent_id);
+        }
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_blocker(&mut game, blocker_id, *attacker_id);
+        }
+        declare_blockers_step_end(&mut game);
+
+        // Lethal damage goes to the first attacker and the rest to the second one
+        combat_damage_step_start(&mut game);
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let attacker = game.get_card(attackers[0]).unwrap();
+        assert_eq!(attacker.zone, Zone::Graveyard);
+
+        let attacker = game.get_card(attackers[1]).unwrap();
+        assert_eq!(attacker.zone, Zone::Battlefield);
+        assert_eq!(attacker.state.toughness.current, 1);
+
+        let blocker = game.get_card(blocker_id).unwrap();
+        assert_eq!(blocker.zone, Zone::Battlefield);
+        assert_eq!(blocker.state.toughness.current, 3);
+    }
+
+    #[test]
+    fn test_blocker_deals_current_power() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut card = Card::new_creature(player_id, 1, 3);
+        card.static_abilities.insert(StaticAbility::Haste);
+        let attacker_id = game.add_card(card);
+        put_on_battlefield(&mut game, attacker_id);
+
+        let blocker_id = game.add_card(Card::new_creature(opponent_id, 2, 2));
+        put_on_battlefield(&mut game, blocker_id);
+
+        declare_attackers_step_start(&mut game);
+        declare_attacker(&mut game, attacker_id, opponent_id);
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+        declare_blocker(&mut game, blocker_id, attacker_id);
+        declare_blockers_step_end(&mut game);
+        combat_damage_step_start(&mut game);
+
+        // The power of the blocker changes after the combat damage step has started
+        game.get_card(blocker_id).unwrap().state.power.current = 3;
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let attacker = game.get_card(attacker_id).unwrap();
+        assert_eq!(attacker.zone, Zone::Graveyard);
+    }
+
+    #[test]
+    fn test_double_strike_blocker_redistributes_damage() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut attackers = Vec::new();
+        for toughness in [1, 5] {
+            let mut card = Card::new_creature(player_id, 1, toughness);
+            card.static_abilities.insert(StaticAbility::Haste);
+            let attacker_id = game.add_card(card);
+            put_on_battlefield(&mut game, attacker_id);
+            attackers.push(attacker_id);
+        }
+
+        let mut card = Card::new_creature(opponent_id, 3, 4);
+        card.static_abilities.insert(StaticAbility::BlockAny);
+        card.static_abilities.insert(StaticAbility::DoubleStrike);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        declare_attackers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_attacker(&mut game, *attacker_id, opponent_id);
+        }
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_blocker(&mut game, blocker_id, *attacker_id);
+        }
+        declare_blockers_step_end(&mut game);
+        combat_damage_step_start(&mut game);
+
+        // First strike damage destroys the first attacker and deals 2 damage to the second one
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        assert_eq!(game.get_card(attackers[0]).unwrap().zone, Zone::Graveyard);
+        assert_eq!(game.get_card(attackers[1]).unwrap().state.damage, 2);
+
+        // Regular damage is dealt entirely to the remaining attacker
+        combat_damage_step_end(&mut game, AttackType::Regular);
+        assert_eq!(game.get_card(attackers[1]).unwrap().zone, Zone::Graveyard);
+
+        let blocker = game.get_card(blocker_id).unwrap();
+        assert_eq!(blocker.zone, Zone::Battlefield);
+        assert_eq!(blocker.state.damage, 1);
+    }
+
+    #[test]
+    fn test_assign_blocker_damage() {
+        let (mut game, player_id, opponent_id) = Game::new();
+
+        let mut attackers = Vec::new();
+        for _ in 0..2 {
+            let mut card = Card::new_creature(player_id, 1, 3);
+            card.static_abilities.insert(StaticAbility::Haste);
+            let attacker_id = game.add_card(card);
+            put_on_battlefield(&mut game, attacker_id);
+            attackers.push(attacker_id);
+        }
+
+        let mut card = Card::new_creature(opponent_id, 3, 5);
+        card.static_abilities.insert(StaticAbility::BlockAny);
+        let blocker_id = game.add_card(card);
+        put_on_battlefield(&mut game, blocker_id);
+
+        declare_attackers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_attacker(&mut game, *attacker_id, opponent_id);
+        }
+        declare_attackers_step_end(&mut game);
+        declare_blockers_step_start(&mut game);
+        for attacker_id in attackers.iter() {
+            declare_blocker(&mut game, blocker_id, *attacker_id);
+        }
+        declare_blockers_step_end(&mut game);
+        combat_damage_step_start(&mut game);
+
+        // The damage assigned to all attackers cannot exceed the power of the blocker
+        assert!(!assign_blocker_damage(
+            &mut game,
+            blocker_id,
+            attackers[1],
+            1
+        ));
+        assert!(assign_blocker_damage(
+            &mut game,
+            blocker_id,
+            attackers[0],
+            0
+        ));
+        assert!(assign_blocker_damage(
+            &mut game,
+            blocker_id,
+            attackers[1],
+            3
+        ));
+        assert!(!assign_blocker_damage(
+            &mut game,
+            blocker_id,
+            attackers[0],
+            1
+        ));
+        assert!(!assign_blocker_damage(
+            &mut game,
+            blocker_id,
+            attackers[1],
+            -1
+        ));
+
+        combat_damage_step_end(&mut game, AttackType::FirstStrike);
+        combat_damage_step_end(&mut game, AttackType::Regular);
+
+        let attacker = game.get_card(attackers[0]).unwrap();
+        assert_eq!(attacker.zone, Zone::Battlefield);
+        assert_eq!(attacker.state.toughness.current, 3);
+
+        let attacker = game.get_card(attackers[1]).unwrap();
+        assert_eq!(attacker.zone, Zone::Graveyard);
+    }
+
     #[test]
     fn test_assign_combat_damage() {
         let (mut game, player_id, opponent_id) = Game::new();